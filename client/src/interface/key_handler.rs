@@ -86,6 +86,10 @@ pub fn save_keymap() {
         (KeyInput::Thrust, VirtualKeyCode::Up),
         (KeyInput::TurnRight, VirtualKeyCode::Right),
         (KeyInput::TurnLeft, VirtualKeyCode::Left),
+        (KeyInput::Fire, VirtualKeyCode::Space),
+        (KeyInput::Brake, VirtualKeyCode::Down),
+        (KeyInput::QuickSave, VirtualKeyCode::F5),
+        (KeyInput::QuickLoad, VirtualKeyCode::F9),
         (KeyInput::Zoom, VirtualKeyCode::P),
         (KeyInput::DeZoom, VirtualKeyCode::M),
         (KeyInput::CenterCam, VirtualKeyCode::O),
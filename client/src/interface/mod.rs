@@ -1,11 +1,14 @@
 use winit::event::WindowEvent;
 
-use game_logic::World;
+use game_logic::{World, PlayerInput, EntityId, WorldSnapshot, SnapshotFormat, Replay};
 
 use logger::{unexpected, UiLogger};
 use crate::rendering::MainRenderer;
 
-use std::time::Instant;
+use std::time::{Duration, Instant};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::thread::JoinHandle;
 
 mod key_handler;
 
@@ -16,7 +19,7 @@ const DEBUG_CAM_ZOOM: f64 = 1.5;
 
 /// Here is all the logic to interface between the player and the game
 pub struct UserInterface {
-    player_idx: usize,
+    player_id: EntityId,
     pub mouse_pos: cgmath::Point2<f64>,
     last_upd: Instant,
     pub keys: KeyInputHashset,
@@ -27,7 +30,15 @@ pub struct UserInterface {
     free_cam_pos: cgmath::Point2<f64>,
     cam_zoom: f64,
     
-    selected_player: usize,
+    selected_player: EntityId,
+
+    /// The round trip time to the server, none when playing locally
+    rtt: Option<Duration>,
+    /// The world belongs to the server, it can't be replaced by a save or a replay
+    online: bool,
+
+    /// The saved replay being played on its own thread, a long one would freeze the game
+    verification: Option<ReplayVerification>,
     
     gui_logger: UiLogger,
 }
@@ -38,7 +49,7 @@ impl UserInterface {
         // key_handler::save_keymap();
 
         return UserInterface { 
-            player_idx: 0, 
+            player_id: EntityId::NONE, 
             mouse_pos: cgmath::Point2 { x: 0., y: 0. },
             last_upd: Instant::now(),
             keys: KeyInputHashset::load(),
@@ -48,12 +59,24 @@ impl UserInterface {
             free_cam_pos: cgmath::Point2 { x: 0., y: 0. },
             frame_times: Vec::new(),
 
-            selected_player: 0,
+            selected_player: EntityId::NONE,
+
+            rtt: None,
+            online: false,
+
+            verification: None,
             
             gui_logger: UiLogger::new(),
         }
     }
 
+    /// Controls the player given by the server, and shows the round trip time to it while `online`
+    pub fn set_network(&mut self, id: EntityId, online: bool, rtt: Option<Duration>) {
+        self.player_id = id;
+        self.online = online;
+        self.rtt = rtt;
+    }
+
     pub fn update_inputs(&mut self, key_event: &WindowEvent) {
         match key_event {
             WindowEvent::KeyboardInput { .. } => self.keys.handle_key_event(key_event),
@@ -80,18 +103,35 @@ impl UserInterface {
     }
 
     fn handle_player_inputs(&mut self, renderer: &mut MainRenderer, world: &mut World, delta_t: f64) {
-        if let Some(player) = world.players.get_mut(self.player_idx) {
-            for press in self.keys.drain_events() {
+        if world.player(self.player_id).is_none() {  // Controls the first player until told otherwise
+            self.player_id = world.players.first().map_or(EntityId::NONE, |player| player.id);
+        }
+
+        for press in self.keys.drain_events() {
+            match press {
                 //#[cfg(debug_assertions)]  // All the values and the systems are available but we can't switch mode in release mode
-                if press == KeyInput::CenterCam {
-                    self.center_cam = !self.center_cam;
-                }
+                KeyInput::CenterCam => self.center_cam = !self.center_cam,
+                KeyInput::QuickSave => quick_save(world),
+                KeyInput::QuickLoad if self.online => logger::warn(3, "Can't load a game while playing on a server"),
+                KeyInput::QuickLoad => quick_load(world),
+                _ => {},
             }
+        }
+
+        if let Some(player) = world.player(self.player_id).copied() {
 
             if self.center_cam {
-                player.press_forward = self.keys.is_pressed(&KeyInput::Thrust);
-                player.press_left = self.keys.is_pressed(&KeyInput::TurnLeft);
-                player.press_right = self.keys.is_pressed(&KeyInput::TurnRight);
+                let mut input = PlayerInput::new(world.tick() + 1, self.player_id);
+                input.thrust = self.keys.is_pressed(&KeyInput::Thrust);
+                input.turn_left = self.keys.is_pressed(&KeyInput::TurnLeft);
+                input.turn_right = self.keys.is_pressed(&KeyInput::TurnRight);
+                input.fire = self.keys.is_pressed(&KeyInput::Fire);
+                input.brake = self.keys.is_pressed(&KeyInput::Brake);
+
+                if world.input(self.player_id).is_none_or(|active| !active.same_actions(&input)) {
+                    world.queue_input(input);
+                }
+
                 renderer.set_cam_pos(player.pos);
 
                 if self.keys.is_pressed(&KeyInput::Zoom) {
@@ -156,6 +196,10 @@ impl UserInterface {
 
             ui.add(egui::Label::new(format!("FPS: {}", fps)));
 
+            if let Some(rtt) = self.rtt {
+                ui.add(egui::Label::new(format!("Ping: {} ms", rtt.as_millis())));
+            }
+
             if self.center_cam {
                 ui.add(egui::Label::new("Centered Camera"));
             } else {
@@ -167,27 +211,94 @@ impl UserInterface {
             ui.add(egui::Label::new(format!("{} Asteroids", world.asteroids.len())));
         });
 
+        if self.verification.as_ref().is_some_and(|verification| verification.thread.is_finished()) {
+            let verification = self.verification.take().unwrap();
+            if verification.thread.join().is_err() {
+                logger::error(3, "The replay verification crashed");
+            }
+        }
+
+        egui::Window::new("Replay").resizable(true).show(&ctx, |ui| {
+            ui.horizontal(|ui| {
+                if world.is_recording() {
+                    if ui.add(egui::Button::new("Stop recording")).clicked() {
+                        save_replay(world);
+                    }
+                } else if ui.add(egui::Button::new("Record")).clicked() {
+                    world.start_recording();
+                    logger::info(3, "Recording a replay");
+                }
+
+                match &self.verification {
+                    Some(verification) => {
+                        ui.add(egui::ProgressBar::new(verification.progress()).show_percentage());
+                    },
+                    None => if ui.add(egui::Button::new("Verify")).clicked() {
+                        self.verification = Some(verify_replay());
+                    },
+                }
+
+                if ui.add_enabled(!self.online, egui::Button::new("Watch")).clicked() {
+                    watch_replay(world);
+                }
+            });
+        });
+
+        egui::Window::new("Scoreboard").resizable(true).show(&ctx, |ui| {
+            egui::Grid::new("Scoreboard Grid").striped(true).show(ui, |ui| {
+                ui.label("Player");
+                ui.label("Score");
+                ui.label("Lives");
+                ui.label("Asteroids");
+                ui.label("Kills");
+                ui.label("Deaths");
+                ui.label("Time Alive");
+                ui.end_row();
+
+                for (id, score) in world.scoreboard().ranking() {
+                    ui.label(format!("Player {}", id));
+                    ui.label(format!("{}", score.score()));
+                    ui.label(format!("{}", score.lives));
+                    ui.label(format!("{}", score.asteroid_kills()));
+                    ui.label(format!("{}", score.player_kills));
+                    ui.label(format!("{}", score.deaths));
+                    ui.label(format!("{:.0}s", score.survival_time.as_secs_f32()));
+                    ui.end_row();
+                }
+            });
+        });
+
         egui::Window::new("Player Info").resizable(true).show(&ctx, |ui| {
             ui.add(egui::Label::new(format!("{} Players", world.players.len())));
 
+            let ids = world.player_ids();
+            // Falls back on the first player when the selected one left
+            let selected_idx = ids.iter().position(|id| *id == self.selected_player).unwrap_or(0);
+
             ui.horizontal(|ui| {
-                if ui.add(egui::Button::new("<<")).clicked() && self.selected_player > 0 {
-                    self.selected_player -= 1;
+                if ui.add(egui::Button::new("<<")).clicked() && selected_idx > 0 {
+                    self.selected_player = ids[selected_idx - 1];
                 };
 
-                ui.add(egui::Label::new(format!("Player {}", self.selected_player)));
+                ui.add(egui::Label::new(format!("Player {}", ids.get(selected_idx).map_or(String::from("-"), |id| id.to_string()))));
 
-                if ui.add(egui::Button::new(">>")).clicked() {
-                    self.selected_player += 1;
-                    if self.selected_player >= world.players.len() {
-                        self.selected_player = world.players.len() - 1;
-                    }
+                if ui.add(egui::Button::new(">>")).clicked() && selected_idx + 1 < ids.len() {
+                    self.selected_player = ids[selected_idx + 1];
                 };
             });
 
-            if let Some(player) = world.players.get_mut(self.selected_player) {
+            let now = world.time();
+            let n_player_img = world.n_player_img;
+            let config = *world.config();
+            if let Some(player) = ids.get(selected_idx).and_then(|id| world.player_mut(*id)) {
+                match player.state {
+                    game_logic::PlayerState::Alive => ui.label(format!("Health {:.0} / {:.0}", player.health, game_logic::Player::MAX_HEALTH)),
+                    game_logic::PlayerState::Dead { respawn_time } => ui.label(format!("Dead, respawning in {:.1}s", respawn_time.saturating_sub(now).as_secs_f32())),
+                    game_logic::PlayerState::Eliminated => ui.label("Eliminated"),
+                };
+                let chunk_pos = config.chunk_of(player.pos);
                 ui.add(egui::Label::new(format!("Pos x: {:.2} y: {:.2}", player.pos.x, player.pos.y)));
-                ui.label(format!("Chunk pos {:?}", game_logic::asteroids::chunk_pos_from_pos(player.pos)));
+                ui.label(format!("Chunk pos {:?}", chunk_pos));
                 ui.add(egui::Label::new(format!("Vel x: {:.2} y: {:.2}", player.vel.x, player.vel.y)));
                 ui.add(egui::Label::new(format!("Speed {:.2}", (player.vel.x * player.vel.x + player.vel.y * player.vel.y).sqrt())));
 
@@ -233,7 +344,7 @@ impl UserInterface {
 
                     ui.add(egui::Label::new(&format!("Img {}", player.player_img)));
 
-                    if ui.add(egui::Button::new("+")).clicked() && player.player_img < world.n_player_img -1 {
+                    if ui.add(egui::Button::new("+")).clicked() && player.player_img < n_player_img -1 {
                         player.player_img += 1;
                     }
                 });
@@ -242,11 +353,94 @@ impl UserInterface {
     }
 }
 
+const QUICK_SAVE_PATH: &str = "quicksave.bin";
+
+fn quick_save(world: &World) {
+    match world.snapshot().save(QUICK_SAVE_PATH, SnapshotFormat::Binary) {
+        Ok(()) => logger::info(3, "Game saved"),
+        Err(err) => logger::error(3, format!("Unable to save the game: {}", err)),
+    }
+}
+
+/// Replaces the world with the quicksave, the world keeps running on real time
+fn quick_load(world: &mut World) {
+    match WorldSnapshot::load(QUICK_SAVE_PATH) {
+        Ok(snapshot) => {
+            *world = World::from_snapshot(snapshot);
+            logger::info(3, "Game loaded");
+        },
+        Err(err) => logger::error(3, format!("Unable to load the game: {}", err)),
+    }
+}
+
+const REPLAY_PATH: &str = "replay.bin";
+
+fn save_replay(world: &mut World) {
+    if let Some(replay) = world.stop_recording() {
+        match replay.save(REPLAY_PATH) {
+            Ok(()) => logger::info(3, format!("Replay of {} ticks saved", replay.n_ticks)),
+            Err(err) => logger::error(3, format!("Unable to save the replay: {}", err)),
+        }
+    }
+}
+
+/// A verification of the saved replay running on its own thread
+struct ReplayVerification {
+    /// The ticks played so far
+    played: Arc<AtomicU64>,
+    /// The ticks of the replay, 0 until it is loaded
+    n_ticks: Arc<AtomicU64>,
+    thread: JoinHandle<()>,
+}
+
+impl ReplayVerification {
+    /// Between 0 and 1
+    fn progress(&self) -> f32 {
+        let n_ticks = self.n_ticks.load(Ordering::Relaxed);
+        if n_ticks == 0 {
+            return 0.;
+        }
+
+        return self.played.load(Ordering::Relaxed) as f32 / n_ticks as f32;
+    }
+}
+
+/// Plays the saved replay headlessly in the background and checks it ends in the recorded state
+fn verify_replay() -> ReplayVerification {
+    let played = Arc::new(AtomicU64::new(0));
+    let n_ticks = Arc::new(AtomicU64::new(0));
+
+    let (thread_played, thread_n_ticks) = (played.clone(), n_ticks.clone());
+    let thread = std::thread::spawn(move || {
+        let result = Replay::load(REPLAY_PATH).and_then(|replay| {
+            thread_n_ticks.store(replay.n_ticks, Ordering::Relaxed);
+            replay.verify_with_progress(|n| thread_played.store(n, Ordering::Relaxed))
+        });
+
+        match result {
+            Ok(_) => logger::info(3, "Replay verified, the simulation is deterministic"),
+            Err(err) => logger::error(3, format!("Replay verification failed: {}", err)),
+        }
+    });
+
+    return ReplayVerification { played, n_ticks, thread };
+}
+
+/// Replaces the world with the start of the saved replay, the inputs play as it runs
+fn watch_replay(world: &mut World) {
+    match Replay::load(REPLAY_PATH) {
+        Ok(replay) => *world = replay.start_world(),
+        Err(err) => logger::error(3, format!("Unable to load the replay: {}", err)),
+    }
+}
+
 #[derive(Debug, Clone, Copy, Hash, PartialEq, Eq, PartialOrd, Ord, serde::Deserialize, serde::Serialize)]
 pub enum KeyInput {
     Thrust, // Player thrust
     TurnRight,
     TurnLeft,
+    Fire,
+    Brake,
     Zoom,
     DeZoom,
     CenterCam,
@@ -254,4 +448,6 @@ pub enum KeyInput {
     CamLeft,
     CamRight,
     CamDown,
+    QuickSave,
+    QuickLoad,
 }
\ No newline at end of file
@@ -2,13 +2,18 @@ mod rendering;
 mod debug;
 mod interface;
 mod math;
+mod network;
 
 use rendering::MainRenderer;
+use network::NetworkClient;
+use web_types::{ConnectionState, PlayerLook};
 
 use winit::{event::{Event, WindowEvent, KeyboardInput, ElementState, VirtualKeyCode}, event_loop::{ControlFlow, EventLoop}, window::Window};
 
 use logger::Log;
 
+use std::net::ToSocketAddrs;
+
 fn main() {
     env_logger::init();
 
@@ -19,8 +24,11 @@ fn main() {
     let (window, event_loop) = setup_window_and_event_loop();
 
     let mut renderer = pollster::block_on(MainRenderer::new(&window, 1., cgmath::Point2 { x: 0., y: 0. }));
-    let mut world = game_logic::World::new_img_auto();
+    let mut world = game_logic::World::new_img_auto(game_logic::WorldConfig::load_or_default("world_config.json"));
     let mut interface = interface::UserInterface::new();
+    // The server gives a new player, it looks like the local one
+    let mut network = connect_from_args(world.players.first().map(PlayerLook::of).unwrap_or_default());
+
     event_loop.run(move |event, _, control_flow| {
         renderer.handle_event(&event); // Necessary for egui
 
@@ -33,6 +41,9 @@ fn main() {
                     virtual_keycode: Some(VirtualKeyCode::Escape),
                     ..
                 }, .. } => {
+                    if let Some(client) = &mut network {
+                        client.disconnect(&mut world);
+                    }
                     *control_flow = ControlFlow::Exit;
                 },
     
@@ -62,6 +73,18 @@ fn main() {
             Event::RedrawRequested(window_id) if window.id() == window_id => {
     
                 let gui_context = renderer.get_gui_context();
+
+                if let Some(client) = &mut network {
+                    match client.update(&mut world) {
+                        ConnectionState::Connecting => {},
+                        ConnectionState::Connected => interface.set_network(client.player(), true, client.rtt()),
+                        // Falls back on local play, the world stays as it was
+                        ConnectionState::Disconnected(_) => {
+                            interface.set_network(client.player(), false, None);
+                            network = None;
+                        },
+                    }
+                }
                 
                 interface.update(&mut world, &mut renderer, gui_context);
                 world.update();
@@ -83,6 +106,28 @@ fn main() {
     });
 }
 
+/// `--connect address` plays on the server at `address` instead of locally
+fn connect_from_args(look: PlayerLook) -> Option<NetworkClient> {
+    let args: Vec<String> = std::env::args().collect();
+    let addr = args.iter().position(|arg| arg == "--connect").and_then(|idx| args.get(idx + 1))?;
+
+    let addr = match addr.to_socket_addrs().map(|mut addrs| addrs.next()) {
+        Ok(Some(addr)) => addr,
+        Ok(None) | Err(_) => {
+            logger::error(0, format!("Invalid server address {}, playing locally", addr));
+            return None;
+        },
+    };
+
+    return match NetworkClient::connect(addr, look) {
+        Ok(client) => Some(client),
+        Err(err) => {
+            logger::error(0, format!("Unable to connect to {}: {}, playing locally", addr, err));
+            None
+        },
+    };
+}
+
 fn setup_window_and_event_loop() -> (Window, EventLoop<()>) {
     let event_loop = winit::event_loop::EventLoop::new();
     let window = winit::window::WindowBuilder::new().build(&event_loop).unwrap();
@@ -0,0 +1,450 @@
+use std::io::ErrorKind;
+use std::net::{SocketAddr, UdpSocket};
+use std::time::{Duration, Instant};
+
+use fnv::{FnvHashMap as HashMap, FnvHashSet as HashSet};
+
+use game_logic::{World, Player, PlayerInput, Asteroid, Projectile, EntityId, WORLD_UPD_RATE};
+use web_types::{UpMsgBox, DownMsgBox, GameUpdate, PlayerLook, WorldHash, HASH_INTERVAL_TICKS, Connection, ConnectionState, DisconnectReason, Timestamp, ClockSync, MAX_PACKET_SIZE};
+
+/// How often the connection request is sent again while the server doesn't answer
+const CONNECT_RETRY: Duration = Duration::from_millis(500);
+/// The server sends every player each tick, one not heard of for this many ticks left without its disconnection arriving
+const REMOTE_PLAYER_TIMEOUT_TICKS: u32 = 30;
+
+/// A chunk snapshot whose parts are still arriving
+#[derive(Debug, Clone)]
+struct PendingChunk {
+    time: Timestamp,
+    parts: Vec<Option<Vec<Asteroid>>>,
+}
+
+/// Plays the local world as a client of a server
+/// The ids of the server's world and of the local world are different, the players are matched by `ids`
+/// While connected the world has no authority, its asteroids are the ones of the server, matched by `asteroid_ids`
+pub struct NetworkClient {
+    socket: UdpSocket,
+    server: SocketAddr,
+    connection: Connection,
+    /// The protocol clock, synced with the keep alives
+    clock: ClockSync,
+    last_connect_try: Instant,
+    /// Given to the player on connection, the server keeps it for the whole session
+    look: PlayerLook,
+    /// The id of the local player in the server's world, none until connected
+    server_id: EntityId,
+    /// The local id of every player of the server, by server id
+    ids: HashMap<EntityId, EntityId>,
+    /// The time of the last update of every player of the server, by server id
+    last_updates: HashMap<EntityId, Timestamp>,
+    /// When every other player of the server was last heard of, by server id
+    last_heard: HashMap<EntityId, Instant>,
+    /// The server ids of the players that left, the updates arriving after their disconnection are ignored
+    /// The server never reuses an id, they can be kept for the whole session
+    departed: HashSet<EntityId>,
+    /// The last tick of the server whose hash was sent
+    last_hashed_tick: u64,
+    /// The local id of every asteroid of the server, by server id
+    asteroid_ids: HashMap<EntityId, EntityId>,
+    /// The time of the last snapshot applied to every chunk, the older ones arriving late are ignored
+    chunk_times: HashMap<(i64, i64), Timestamp>,
+    pending_chunks: HashMap<(i64, i64), PendingChunk>,
+    /// The local player, controlled by the interface
+    player: EntityId,
+    last_sent_input: Option<PlayerInput>,
+    last_player_update: Instant,
+}
+
+impl NetworkClient {
+    /// Starts connecting to the server, `update` carries on the handshake
+    pub fn connect(server: SocketAddr, look: PlayerLook) -> std::io::Result<NetworkClient> {
+        let local: SocketAddr = if server.is_ipv4() { ([0, 0, 0, 0], 0).into() } else { ([0; 16], 0).into() };
+        let socket = UdpSocket::bind(local)?;
+        socket.connect(server)?;
+        socket.set_nonblocking(true)?;
+
+        let now = Instant::now();
+        let client = NetworkClient {
+            socket,
+            server,
+            connection: Connection::connecting(now),
+            clock: ClockSync::new(now),
+            last_connect_try: now,
+            look,
+            server_id: EntityId::NONE,
+            ids: HashMap::default(),
+            last_updates: HashMap::default(),
+            last_heard: HashMap::default(),
+            departed: HashSet::default(),
+            last_hashed_tick: 0,
+            asteroid_ids: HashMap::default(),
+            chunk_times: HashMap::default(),
+            pending_chunks: HashMap::default(),
+            player: EntityId::NONE,
+            last_sent_input: None,
+            last_player_update: now,
+        };
+        client.send(UpMsgBox::NewConnection { look });
+
+        logger::info(3, format!("Connecting to {}", server));
+
+        return Ok(client);
+    }
+
+    /// The local id of the player controlled by this client, none until connected
+    pub fn player(&self) -> EntityId {
+        return self.player;
+    }
+
+    /// The round trip time to the server, once it echoed a keep alive
+    pub fn rtt(&self) -> Option<Duration> {
+        return self.connection.rtt();
+    }
+
+    /// Handles the messages of the server and sends the state of the local player, to call every frame
+    pub fn update(&mut self, world: &mut World) -> ConnectionState {
+        if let ConnectionState::Disconnected(_) = self.connection.state() {
+            return self.connection.state();
+        }
+
+        self.receive(world);
+
+        let now = Instant::now();
+
+        match self.connection.update(now) {
+            ConnectionState::Connecting => {
+                if now.duration_since(self.last_connect_try) > CONNECT_RETRY {
+                    self.send(UpMsgBox::NewConnection { look: self.look });
+                    self.last_connect_try = now;
+                }
+            },
+            ConnectionState::Connected => {
+                self.drop_silent_players(world, now);
+                self.send_local_player(world, now);
+            },
+            ConnectionState::Disconnected(reason) => {
+                // The other reasons come from a message of the server, they were already reported
+                if reason == DisconnectReason::TimedOut {
+                    logger::warn(3, format!("Lost the connection to {}, playing locally", self.server));
+                }
+                self.fall_back(world);
+            },
+        }
+
+        return self.connection.state();
+    }
+
+    /// Tells the server the player leaves, the world keeps running locally
+    pub fn disconnect(&mut self, world: &mut World) {
+        if self.connection.is_connected() {
+            self.send(UpMsgBox::Disconect);
+            logger::info(3, format!("Player {} left {}", self.server_id, self.server));
+        }
+
+        self.connection.close(DisconnectReason::Closed);
+        self.fall_back(world);
+    }
+
+    fn receive(&mut self, world: &mut World) {
+        let mut buf = [0; MAX_PACKET_SIZE];
+
+        loop {
+            let len = match self.socket.recv(&mut buf) {
+                Ok(len) => len,
+                Err(err) if err.kind() == ErrorKind::WouldBlock => return,
+                // The server isn't listening, the connection timeout handles it
+                Err(err) if err.kind() == ErrorKind::ConnectionRefused || err.kind() == ErrorKind::ConnectionReset => return,
+                Err(err) => {
+                    logger::error(3, format!("Unable to receive from {}: {}", self.server, err));
+                    return;
+                },
+            };
+
+            match DownMsgBox::from_bytes(&buf[..len]) {
+                Ok(msg) => {
+                    self.connection.heard(Instant::now());
+                    self.handle_msg(world, msg);
+                },
+                Err(err) => logger::warn(3, format!("Invalid message from {}: {}", self.server, err)),
+            }
+        }
+    }
+
+    fn handle_msg(&mut self, world: &mut World, msg: DownMsgBox) {
+        match msg {
+            DownMsgBox::ConnectionAcknowleged { your_id, world_seed, world_config, server_time, .. } => {
+                if self.connection.state() == ConnectionState::Connecting {
+                    // Answers one of the requests, taken as the last one, the keep alives refine it
+                    let now = Instant::now();
+                    self.clock.sample(self.clock.local_time(self.last_connect_try), server_time, now);
+
+                    // The server's world replaces the local one, its asteroids come with the chunk snapshots
+                    *world = World::new(world.n_asteroid_img, world.n_player_img, world_seed, world_config);
+                    for id in world.player_ids() {
+                        world.remove_player(id);
+                    }
+                    let asteroids: Vec<_> = world.asteroids.iter().map(|asteroid| asteroid.id).collect();
+                    world.remove_asteroids(&asteroids);
+                    world.set_authority(false);
+
+                    self.ids.clear();
+                    self.last_updates.clear();
+                    self.last_heard.clear();
+                    self.departed.clear();
+                    self.last_hashed_tick = 0;
+                    self.forget_asteroids();
+                    let mut player = Player::new();
+                    self.look.apply(&mut player);
+                    self.player = world.add_player(player);
+                    self.ids.insert(your_id, self.player);
+                    self.server_id = your_id;
+                    self.connection.acknowledged(now);
+
+                    logger::info(3, format!("Connected to {} as player {}", self.server, your_id));
+                }
+            },
+            DownMsgBox::GameUpdate(update) => {
+                if self.connection.is_connected() {
+                    self.apply_update(world, update);
+                }
+            },
+            DownMsgBox::KeepAlive { time, server_time } => {
+                let now = Instant::now();
+                // This client never sent a time its clock hasn't reached yet
+                if time > self.clock.local_time(now) {
+                    logger::warn(3, format!("Keep alive from {} echoes a time that wasn't sent", self.server));
+                    return;
+                }
+
+                if let Some(sent) = self.clock.instant_of(time) {
+                    self.connection.echoed(sent, now);
+                }
+                self.clock.sample(time, server_time, now);
+            },
+            DownMsgBox::Unrecognised => {
+                logger::warn(3, format!("{} doesn't know this client anymore, playing locally", self.server));
+                self.connection.close(DisconnectReason::Refused);
+            },
+            DownMsgBox::ServerClosing => {
+                logger::warn(3, format!("{} is closing, playing locally", self.server));
+                self.connection.close(DisconnectReason::Closed);
+            },
+        }
+    }
+
+    fn apply_update(&mut self, world: &mut World, update: GameUpdate) {
+        match update {
+            // A player update of an unknown player means its arrival was lost, it is added the same way with the default look
+            GameUpdate::NewPlayer { id, player } | GameUpdate::PlayerUpdate { id, player, .. } => {
+                if self.departed.contains(&id) {
+                    return;
+                }
+
+                let joined = matches!(update, GameUpdate::NewPlayer { .. });
+
+                // UDP doesn't keep the order, an update older than the last one applied is outdated
+                if let GameUpdate::PlayerUpdate { time, .. } = update {
+                    if self.last_updates.get(&id).is_some_and(|last| time < *last) {
+                        return;
+                    }
+                    self.last_updates.insert(id, time);
+                }
+                self.last_heard.insert(id, Instant::now());
+
+                let local_id = match self.ids.get(&id) {
+                    Some(local_id) => *local_id,
+                    None => {
+                        let local_id = world.add_player(player);
+                        self.ids.insert(id, local_id);
+                        local_id
+                    },
+                };
+
+                if let Some(local) = world.player_mut(local_id) {
+                    if local_id == self.player {
+                        // The local player moves on its own, the server decides of what happens to it
+                        local.health = player.health;
+                        local.state = player.state;
+                        local.invulnerable_until = player.invulnerable_until;
+                    } else {
+                        let mut player = player;
+                        player.id = local_id;
+                        // Only the arrival of the player carries its look
+                        if !joined {
+                            PlayerLook::of(local).apply(&mut player);
+                        }
+                        *local = player;
+                    }
+                }
+
+                if let GameUpdate::PlayerUpdate { time, .. } = update {
+                    self.send_hash(world, time);
+                }
+            },
+            GameUpdate::PlayerDisconnect { id } => {
+                if id != self.server_id {
+                    self.departed.insert(id);
+                    self.forget(world, id);
+                }
+            },
+            GameUpdate::ChunkState { chunk, time, part, n_parts, asteroids } => {
+                if let Some(asteroids) = self.assemble_chunk(chunk, time, part, n_parts, asteroids) {
+                    self.apply_chunk(world, chunk, &asteroids);
+                }
+            },
+            GameUpdate::NewProjectile { projectile } => {
+                // The local player fired its own projectiles already
+                if projectile.owner == self.server_id {
+                    return;
+                }
+
+                let owner = self.ids.get(&projectile.owner).copied().unwrap_or(EntityId::NONE);
+                world.add_projectile(Projectile { owner, expire_time: world.time() + projectile.expire_time, ..projectile });
+            },
+        }
+    }
+
+    /// Keeps the part of a chunk snapshot, gives the whole snapshot once its last part arrived
+    fn assemble_chunk(&mut self, chunk: (i64, i64), time: Timestamp, part: u8, n_parts: u8, asteroids: Vec<Asteroid>) -> Option<Vec<Asteroid>> {
+        if part >= n_parts || self.chunk_times.get(&chunk).is_some_and(|last| time <= *last) {
+            return None;
+        }
+
+        let fresh = || PendingChunk { time, parts: vec![None; n_parts as usize] };
+        let pending = self.pending_chunks.entry(chunk).or_insert_with(fresh);
+        if time < pending.time {
+            return None;
+        }
+        // The parts of an older snapshot are dropped for the newer one
+        if time > pending.time || pending.parts.len() != n_parts as usize {
+            *pending = fresh();
+        }
+
+        pending.parts[part as usize] = Some(asteroids);
+        if pending.parts.iter().any(|part| part.is_none()) {
+            return None;
+        }
+
+        let pending = self.pending_chunks.remove(&chunk)?;
+        self.chunk_times.insert(chunk, time);
+
+        return Some(pending.parts.into_iter().flatten().flatten().collect());
+    }
+
+    /// Makes the asteroids of the chunk the ones of the snapshot, the others found in the chunk are gone on the server
+    fn apply_chunk(&mut self, world: &mut World, chunk: (i64, i64), asteroids: &[Asteroid]) {
+        let n_asteroid_img = world.n_asteroid_img.max(1);
+
+        for asteroid in asteroids {
+            let img_idx = asteroid.img_idx.rem_euclid(n_asteroid_img);
+
+            match self.asteroid_ids.get(&asteroid.id).and_then(|local_id| world.asteroid_mut(*local_id)) {
+                Some(local) => *local = Asteroid { id: local.id, img_idx, spawn_time: local.spawn_time, ..*asteroid },
+                None => {
+                    let local_id = world.add_asteroid(Asteroid { img_idx, spawn_time: world.time().as_secs_f32(), ..*asteroid });
+                    self.asteroid_ids.insert(asteroid.id, local_id);
+                },
+            }
+        }
+
+        let listed: HashSet<_> = asteroids.iter().map(|asteroid| asteroid.id).collect();
+        let mut gone = Vec::new();
+        self.asteroid_ids.retain(|id, local_id| {
+            let in_chunk = match world.asteroid(*local_id) {
+                Some(local) => world.config().chunk_of(local.pos) == chunk,
+                None => return false,
+            };
+            if in_chunk && !listed.contains(id) {
+                gone.push(*local_id);
+                return false;
+            }
+            return true;
+        });
+        world.remove_asteroids(&gone);
+    }
+
+    fn forget_asteroids(&mut self) {
+        self.asteroid_ids.clear();
+        self.chunk_times.clear();
+        self.pending_chunks.clear();
+    }
+
+    /// Sends the hash of the players once every one of them got the update of a hashed tick
+    fn send_hash(&mut self, world: &World, time: Timestamp) {
+        let tick = time.tick();
+        if !tick.is_multiple_of(HASH_INTERVAL_TICKS) || tick <= self.last_hashed_tick {
+            return;
+        }
+        if self.ids.keys().any(|id| self.last_updates.get(id) != Some(&time)) {
+            return;
+        }
+
+        let players = self.ids.iter().filter_map(|(id, local_id)| world.player(*local_id).map(|player| (*id, player)));
+        self.send(UpMsgBox::WorldHash(WorldHash::of_players(tick, players)));
+        self.last_hashed_tick = tick;
+    }
+
+    /// Removes the players whose disconnection was lost, they come back with their next update if they were only delayed
+    fn drop_silent_players(&mut self, world: &mut World, now: Instant) {
+        let timeout = WORLD_UPD_RATE * REMOTE_PLAYER_TIMEOUT_TICKS;
+        let silent: Vec<_> = self.last_heard.iter()
+            .filter_map(|(id, heard)| (*id != self.server_id && now.saturating_duration_since(*heard) > timeout).then_some(*id))
+            .collect();
+
+        for id in silent {
+            self.forget(world, id);
+        }
+    }
+
+    fn forget(&mut self, world: &mut World, id: EntityId) {
+        self.last_updates.remove(&id);
+        self.last_heard.remove(&id);
+        if let Some(local_id) = self.ids.remove(&id) {
+            world.remove_player(local_id);
+        }
+    }
+
+    fn send_local_player(&mut self, world: &World, now: Instant) {
+        if self.connection.keep_alive_due(now) {
+            self.send(UpMsgBox::KeepAlive { time: self.clock.local_time(now) });
+        }
+
+        // The changes of input are sent at once, and the input again with every update since a lost one is never resent
+        let input = world.input(self.player).copied();
+        let changed = input.is_some_and(|input| self.last_sent_input.is_none_or(|sent| !sent.same_actions(&input)));
+        let update_due = now.duration_since(self.last_player_update) >= WORLD_UPD_RATE;
+
+        if let Some(input) = input.filter(|_| changed || update_due) {
+            // The server applies it at its own next tick
+            self.send(UpMsgBox::Input { input });
+            self.last_sent_input = Some(input);
+        }
+
+        if update_due {
+            if let Some(player) = world.player(self.player) {
+                // Synced since the acknowledgement
+                let time = self.clock.server_time(now).unwrap_or(Timestamp::ZERO);
+                self.send(UpMsgBox::PlayerUpdate { player: *player, time });
+            }
+            self.last_player_update = now;
+        }
+    }
+
+    /// Forgets the players of the server, the local player and the asteroids stay and the world streams its chunks again
+    fn fall_back(&mut self, world: &mut World) {
+        world.set_authority(true);
+        self.forget_asteroids();
+        self.last_updates.clear();
+        self.last_heard.clear();
+        for (_, local_id) in self.ids.drain() {
+            if local_id != self.player {
+                world.remove_player(local_id);
+            }
+        }
+    }
+
+    fn send(&self, msg: UpMsgBox) {
+        // Errors are the same as a lost packet, the timeouts deal with them
+        let _ = self.socket.send(&msg.to_bytes());
+    }
+}
@@ -3,7 +3,7 @@ use wgpu::util::DeviceExt;
 use game_logic::Asteroid;
 use logger::warn;
 
-use super::{Basic2DVertex, set_zoom_for_quad, load_textures_to_array};
+use super::{Basic2DVertex, QUAD_INDICES, set_zoom_for_quad, overall_data, load_textures_to_array};
 
 fn generate_instances(asteroids: &[Asteroid], cam_pos: cgmath::Point2<f64>) -> Vec<InstanceRaw> {
     let start = std::time::Instant::now();
@@ -250,18 +250,6 @@ const QUAD: &[Basic2DVertex] = &[
     Basic2DVertex { position: [1., 1.], tex_coords: [1., 0.] },
 ];
 
-const QUAD_INDICES: &[u16] = &[
-    0, 1, 2,
-    2, 3, 0,
-];
-
-pub const OPENGL_TO_WGPU_MATRIX: cgmath::Matrix4<f32> = cgmath::Matrix4::new(
-    1., 0., 0., 0., 
-    0., 1., 0., 0., 
-    0., 0., 0.5, 0., 
-    0., 0., 0.5, 1.
-);
-
 #[repr(C)]
 #[derive(Debug, Clone, Copy, bytemuck::Pod, bytemuck::Zeroable)]
 pub struct InstanceRaw {
@@ -308,7 +296,8 @@ impl InstanceRaw {
     fn from_asteroid(asteroid: &Asteroid, cam_pos: cgmath::Point2<f64>) -> InstanceRaw {
         let rot = cgmath::Rad(asteroid.rot);
         let pos = cgmath::Vector3 { x: (asteroid.pos.x - cam_pos.x) as f32, y: (asteroid.pos.y - cam_pos.y) as f32, z: 0.};
-        let model = cgmath::Matrix4::from_translation(pos) * cgmath::Matrix4::<f32>::from_angle_z(rot);
+        let scale = cgmath::Matrix4::from_scale(asteroid.size.scale() as f32);
+        let model = cgmath::Matrix4::from_translation(pos) * cgmath::Matrix4::<f32>::from_angle_z(rot) * scale;
 
         return InstanceRaw {
             model: model.into(),
@@ -316,28 +305,3 @@ impl InstanceRaw {
         };
     }
 }
-
-#[repr(C)]
-#[derive(Debug, Clone, Copy, bytemuck::Pod, bytemuck::Zeroable)]
-struct OveralBuffer {
-    view_proj: [[f32; 4]; 4],
-}
-
-fn overall_data(scr_ratio: f32, zoom: f32) -> OveralBuffer {
-    const N_UNIT: f32 = 1.;
-
-    let unit_w;
-    let unit_h;
-    if scr_ratio > 1. {
-        unit_w = 1. / N_UNIT;
-        unit_h = unit_w / scr_ratio;
-    } else {
-        unit_h = 1. / N_UNIT;
-        unit_w = unit_h * scr_ratio;
-    }
-
-    let deform = cgmath::Matrix4::from_nonuniform_scale(unit_w * zoom, unit_h * zoom, 1.);
-    let mat = OPENGL_TO_WGPU_MATRIX * deform;
-
-    return OveralBuffer { view_proj: mat.into() };
-}
\ No newline at end of file
@@ -1,11 +1,13 @@
 mod asteroid_rendering;
 mod player_rendering;
+mod projectile_rendering;
 mod gui_rendering;
 
 use game_logic::World;
 
 use asteroid_rendering::AsteroidPipeline;
 use player_rendering::PlayerPipeline;
+use projectile_rendering::ProjectilePipeline;
 use gui_rendering::GuiPipeline;
 
 pub struct MainRenderer {
@@ -17,6 +19,7 @@ pub struct MainRenderer {
 
     asteroid_pipeline: AsteroidPipeline,
     player_pipeline: PlayerPipeline,
+    projectile_pipeline: ProjectilePipeline,
     gui_pipeline: GuiPipeline,
     
     cam_pos: cgmath::Point2<f64>,
@@ -60,6 +63,8 @@ impl MainRenderer {
 
         let player_pipeline = PlayerPipeline::new(&device, &queue, &config, &[], zoom);
 
+        let projectile_pipeline = ProjectilePipeline::new(&device, &config, zoom);
+
         let gui_pipeline = GuiPipeline::new(&device, &config, window.scale_factor(), surface.get_supported_formats(&adapter)[0]);
 
         return MainRenderer {
@@ -72,6 +77,7 @@ impl MainRenderer {
             cam_pos,
             zoom,
             player_pipeline,
+            projectile_pipeline,
             gui_pipeline,
         };
     }
@@ -84,6 +90,7 @@ impl MainRenderer {
             self.surface.configure(&self.device, &self.config);
             self.asteroid_pipeline.resize(new_size, &self.queue);
             self.player_pipeline.resize(new_size, &self.queue);
+            self.projectile_pipeline.resize(new_size, &self.queue);
             self.gui_pipeline.resize(new_size, scale_factor);
         }
     }
@@ -116,6 +123,8 @@ impl MainRenderer {
 
         self.asteroid_pipeline.render(&mut encoder, &view);
 
+        self.projectile_pipeline.render(&mut encoder, &view);
+
         self.player_pipeline.render(&mut encoder, &view);
 
         self.gui_pipeline.render(&mut encoder, &view, window, &self.device, &self.queue);
@@ -129,7 +138,8 @@ impl MainRenderer {
     pub fn update(&mut self, game: &World) {
         
         self.asteroid_pipeline.update(&game.asteroids, &self.queue, &self.device, self.cam_pos);
-        self.player_pipeline.update(&game.players, &self.queue, &self.device, &self.cam_pos);
+        self.player_pipeline.update(&game.players, &self.queue, &self.device, &self.cam_pos, game.time());
+        self.projectile_pipeline.update(&game.projectiles, &game.players, &self.queue, &self.device, self.cam_pos);
         
     }
 
@@ -141,6 +151,7 @@ impl MainRenderer {
         self.zoom = new_zoom;
         self.asteroid_pipeline.update_cam_zoom(&self.queue, self.zoom);
         self.player_pipeline.update_cam_zoom(&self.queue, new_zoom);
+        self.projectile_pipeline.update_cam_zoom(&self.queue, new_zoom);
     }
 
     pub fn get_gui_context(&mut self) -> egui::Context {
@@ -180,6 +191,44 @@ impl Basic2DVertex {
     }
 }
 
+/// The two triangles of a quad made of 4 vertices
+const QUAD_INDICES: &[u16] = &[
+    0, 1, 2,
+    2, 3, 0,
+];
+
+const OPENGL_TO_WGPU_MATRIX: cgmath::Matrix4<f32> = cgmath::Matrix4::new(
+    1., 0., 0., 0., 
+    0., 1., 0., 0., 
+    0., 0., 0.5, 0., 
+    0., 0., 0.5, 1.
+);
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy, bytemuck::Pod, bytemuck::Zeroable)]
+struct OveralBuffer {
+    view_proj: [[f32; 4]; 4],
+}
+
+fn overall_data(scr_ratio: f32, zoom: f32) -> OveralBuffer {
+    const N_UNIT: f32 = 1.;
+
+    let unit_w;
+    let unit_h;
+    if scr_ratio > 1. {
+        unit_w = 1. / N_UNIT;
+        unit_h = unit_w / scr_ratio;
+    } else {
+        unit_h = 1. / N_UNIT;
+        unit_w = unit_h * scr_ratio;
+    }
+
+    let deform = cgmath::Matrix4::from_nonuniform_scale(unit_w * zoom, unit_h * zoom, 1.);
+    let mat = OPENGL_TO_WGPU_MATRIX * deform;
+
+    return OveralBuffer { view_proj: mat.into() };
+}
+
 fn set_zoom_for_quad(quad: &[Basic2DVertex]) -> Vec<Basic2DVertex> {
     const N_WIDTH: f32 = 20.; // The number of asteroids you can put in the width of the screen
 
@@ -5,10 +5,20 @@ use game_logic::Player;
 
 use super::{Basic2DVertex, set_zoom_for_quad, load_textures_to_array};
 
-fn generate_instances(players: &[Player], cam_pos: &cgmath::Point2<f64>) -> Vec<InstanceRaw> {
+/// Dead players aren't drawn and invulnerable ones blink
+fn generate_instances(players: &[Player], cam_pos: &cgmath::Point2<f64>, now: std::time::Duration) -> Vec<InstanceRaw> {
+    const BLINK_PERIOD_MS: u128 = 200;
+
     let mut output = Vec::with_capacity(players.len());
 
     for x in players {
+        if !x.is_alive() {
+            continue;
+        }
+        if x.is_invulnerable(now) && (now.as_millis() / BLINK_PERIOD_MS) % 2 == 1 {
+            continue;
+        }
+
         output.push(InstanceRaw::from_player(x, cam_pos));
     }
 
@@ -52,7 +62,7 @@ impl PlayerPipeline {
 
         let instance_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
             label: Some("Player Instance Buffer"),
-            contents: bytemuck::cast_slice(&generate_instances(players, &cgmath::Point2 { x: 0., y: 0. })),
+            contents: bytemuck::cast_slice(&generate_instances(players, &cgmath::Point2 { x: 0., y: 0. }, std::time::Duration::ZERO)),
             usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::VERTEX,
         });
 
@@ -186,22 +196,28 @@ impl PlayerPipeline {
         queue.write_buffer(&self.overall_buffer, 0, bytemuck::cast_slice(&[overall_data(self.scr_ratio, self.cam_zoom)]));
     }
 
-    pub fn update(&mut self, players: &[Player], queue: &wgpu::Queue, device: &wgpu::Device, cam_pos: &cgmath::Point2<f64>) {
-        if self.n_player != players.len() {
+    /// `now` is the simulated time of the world, for the blinking of invulnerable players
+    pub fn update(&mut self, players: &[Player], queue: &wgpu::Queue, device: &wgpu::Device, cam_pos: &cgmath::Point2<f64>, now: std::time::Duration) {
+        let data = generate_instances(players, cam_pos, now);
+
+        if self.n_player != data.len() {
             self.instance_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
                 label: Some("NEW Player Instance Buffer"),
-                contents: bytemuck::cast_slice(&generate_instances(players, cam_pos)),
+                contents: bytemuck::cast_slice(&data),
                 usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::VERTEX,
             });
 
-            self.n_player = players.len();
+            self.n_player = data.len();
         } else {
-            let data = generate_instances(players, cam_pos);
             queue.write_buffer(&self.instance_buffer, 0, bytemuck::cast_slice(&data));
         }
     }
 
     pub fn render(&mut self, encoder: &mut wgpu::CommandEncoder, view: &wgpu::TextureView) {
+        if self.n_player == 0 {  // Everyone is dead, the buffer is empty
+            return;
+        }
+
         let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
             label: Some("Player Render Pass"),
             color_attachments: &[
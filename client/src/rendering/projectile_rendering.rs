@@ -0,0 +1,275 @@
+use wgpu::util::DeviceExt;
+
+use game_logic::{Player, Projectile};
+
+use super::{Basic2DVertex, QUAD_INDICES, overall_data};
+
+fn generate_instances(projectiles: &[Projectile], players: &[Player], cam_pos: cgmath::Point2<f64>) -> Vec<InstanceRaw> {
+    let mut output = Vec::with_capacity(projectiles.len());
+
+    for x in projectiles {
+        output.push(InstanceRaw::from_projectile(x, players, cam_pos));
+    }
+
+    return output;
+}
+
+pub struct ProjectilePipeline {
+    render_pipeline: wgpu::RenderPipeline,
+
+    index_buffer: wgpu::Buffer,
+    vertex_buffer: wgpu::Buffer,
+    instance_buffer: wgpu::Buffer,
+    overall_buffer: wgpu::Buffer,
+    bind_group: wgpu::BindGroup,
+
+    scr_ratio: f32,
+    cam_zoom: f32,
+
+    n_projectile: usize,
+    /// The number of instances the instance buffer can hold
+    instance_capacity: usize,
+}
+
+impl ProjectilePipeline {
+    pub fn new(device: &wgpu::Device, config: &wgpu::SurfaceConfiguration, zoom: f64) -> ProjectilePipeline {
+        let zoom = zoom as f32;
+
+        let scr_ratio = config.height as f32 / config.width as f32;
+
+        let vertex_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Projectile Vertex Buffer"),
+            contents: bytemuck::cast_slice(&projectile_quad()),
+            usage: wgpu::BufferUsages::VERTEX,
+        });
+
+        let index_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Projectile Index Buffer"),
+            contents: bytemuck::cast_slice(QUAD_INDICES),
+            usage: wgpu::BufferUsages::INDEX,
+        });
+
+        let instance_capacity = Self::BASE_CAPACITY;
+        let instance_buffer = create_instance_buffer(device, instance_capacity);
+
+        let overall_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Projectile Overall Buffer"),
+            contents: bytemuck::cast_slice(&[overall_data(scr_ratio, zoom)]),
+            usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::UNIFORM,
+        });
+
+        let shader_mod = device.create_shader_module(wgpu::include_wgsl!("projectile_shader.wgsl"));
+
+        let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("Projectile Bind Group Layout"),
+            entries: &[
+                wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::VERTEX,
+                    ty: wgpu::BindingType::Buffer { 
+                        ty: wgpu::BufferBindingType::Uniform, 
+                        has_dynamic_offset: false, 
+                        min_binding_size: None, 
+                    },
+                    count: None,
+                },
+            ],
+        });
+
+        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Projectile Bind Group"),
+            layout: &bind_group_layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: wgpu::BindingResource::Buffer(overall_buffer.as_entire_buffer_binding()),
+                },
+            ],
+        });
+
+        let render_pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("Projectile Pipeline Layout"),
+            bind_group_layouts: &[
+                &bind_group_layout
+            ],
+            push_constant_ranges: &[],
+        });
+
+        let render_pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("Projectile Render Pipeline"),
+            layout: Some(&render_pipeline_layout),
+            vertex: wgpu::VertexState { 
+                module: &shader_mod, 
+                entry_point: "vs_main", 
+                buffers: &[
+                    Basic2DVertex::desc(),
+                    InstanceRaw::desc(),
+                ] 
+            },
+            primitive: wgpu::PrimitiveState { 
+                topology: wgpu::PrimitiveTopology::TriangleList, 
+                strip_index_format: None, 
+                front_face: wgpu::FrontFace::Ccw, 
+                cull_mode: None, 
+                unclipped_depth: false, 
+                polygon_mode: wgpu::PolygonMode::Fill, 
+                conservative: false 
+            },
+            depth_stencil: None,
+            multisample: wgpu::MultisampleState { 
+                count: 1, 
+                mask: !0, 
+                alpha_to_coverage_enabled: false 
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: &shader_mod,
+                entry_point: "fs_main",
+                targets: &[
+                    Some(wgpu::ColorTargetState {
+                        format: config.format,
+                        blend: Some(wgpu::BlendState::ALPHA_BLENDING),
+                        write_mask: wgpu::ColorWrites::all(),
+                    })
+                ],
+            }),
+            multiview: None,
+        });
+
+        return ProjectilePipeline { 
+            render_pipeline, 
+            index_buffer, 
+            vertex_buffer, 
+            instance_buffer, 
+            overall_buffer, 
+            bind_group, 
+            scr_ratio, 
+            cam_zoom: zoom, 
+            n_projectile: 0, 
+            instance_capacity,
+        };
+    }
+
+    /// The number of projectiles the instance buffer holds at first
+    const BASE_CAPACITY: usize = 64;
+
+    pub fn resize(&mut self, new_size: winit::dpi::PhysicalSize<u32>, queue: &wgpu::Queue) {
+        self.scr_ratio = new_size.height as f32 / new_size.width as f32;
+        queue.write_buffer(&self.overall_buffer, 0, bytemuck::cast_slice(&[overall_data(self.scr_ratio, self.cam_zoom)]));
+    }
+
+    pub fn update_cam_zoom(&mut self, queue: &wgpu::Queue, new_zoom: f64) {
+        self.cam_zoom = new_zoom as f32;
+        queue.write_buffer(&self.overall_buffer, 0, bytemuck::cast_slice(&[overall_data(self.scr_ratio, self.cam_zoom)]));
+    }
+
+    /// The buffer is only grown, as the number of projectiles changes almost every frame
+    pub fn update(&mut self, projectiles: &[Projectile], players: &[Player], queue: &wgpu::Queue, device: &wgpu::Device, cam_pos: cgmath::Point2<f64>) {
+        if projectiles.len() > self.instance_capacity {
+            self.instance_capacity = projectiles.len().next_power_of_two();
+            self.instance_buffer = create_instance_buffer(device, self.instance_capacity);
+        }
+
+        self.n_projectile = projectiles.len();
+
+        if self.n_projectile > 0 {
+            let data = generate_instances(projectiles, players, cam_pos);
+            queue.write_buffer(&self.instance_buffer, 0, bytemuck::cast_slice(&data));
+        }
+    }
+
+    pub fn render(&self, encoder: &mut wgpu::CommandEncoder, view: &wgpu::TextureView) {
+        if self.n_projectile == 0 {
+            return;
+        }
+
+        let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+            label: Some("Projectile Render Pass"),
+            color_attachments: &[
+                Some(wgpu::RenderPassColorAttachment {
+                    view,
+                    resolve_target: None,
+                    ops: wgpu::Operations { 
+                        load: wgpu::LoadOp::Load, 
+                        store: true 
+                    },
+                })
+            ],
+            depth_stencil_attachment: None,
+        });
+
+        render_pass.set_pipeline(&self.render_pipeline);
+
+        render_pass.set_vertex_buffer(0, self.vertex_buffer.slice(..));
+        render_pass.set_vertex_buffer(1, self.instance_buffer.slice(..));
+
+        render_pass.set_index_buffer(self.index_buffer.slice(..), wgpu::IndexFormat::Uint16);
+
+        render_pass.set_bind_group(0, &self.bind_group, &[]);
+
+        render_pass.draw_indexed(0..QUAD_INDICES.len() as u32, 0, 0..self.n_projectile as u32);
+    }
+}
+
+fn create_instance_buffer(device: &wgpu::Device, capacity: usize) -> wgpu::Buffer {
+    return device.create_buffer(&wgpu::BufferDescriptor {
+        label: Some("Projectile Instance Buffer"),
+        size: (capacity * std::mem::size_of::<InstanceRaw>()) as wgpu::BufferAddress,
+        usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::VERTEX,
+        mapped_at_creation: false,
+    });
+}
+
+/// A quad the size of the hitbox of a projectile, so what is seen is what hits
+fn projectile_quad() -> [Basic2DVertex; 4] {
+    let r = Projectile::RADIUS as f32;
+
+    return [
+        Basic2DVertex { position: [-r, r], tex_coords: [0., 0.] },
+        Basic2DVertex { position: [-r, -r], tex_coords: [0., 1.] },
+        Basic2DVertex { position: [r, -r], tex_coords: [1., 1.] },
+        Basic2DVertex { position: [r, r], tex_coords: [1., 0.] },
+    ];
+}
+
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy, bytemuck::Pod, bytemuck::Zeroable)]
+struct InstanceRaw {
+    position: [f32; 2],
+    color: [f32; 4],
+}
+
+impl InstanceRaw {
+    fn desc<'a>() -> wgpu::VertexBufferLayout<'a> {
+        use std::mem;
+        return wgpu::VertexBufferLayout { 
+            array_stride: mem::size_of::<InstanceRaw>() as wgpu::BufferAddress, 
+            step_mode: wgpu::VertexStepMode::Instance, 
+            attributes: &[
+                wgpu::VertexAttribute { // Position
+                    format: wgpu::VertexFormat::Float32x2,
+                    offset: 0,
+                    shader_location: 2,
+                },
+                wgpu::VertexAttribute { // Color
+                    format: wgpu::VertexFormat::Float32x4,
+                    offset: std::mem::size_of::<[f32; 2]>() as wgpu::BufferAddress,
+                    shader_location: 3,
+                },
+            ],
+        };
+    }
+
+    /// Projectiles take the main accent color of the ship that fired them
+    fn from_projectile(projectile: &Projectile, players: &[Player], cam_pos: cgmath::Point2<f64>) -> InstanceRaw {
+        let color = match players.iter().find(|player| player.id == projectile.owner) {
+            Some(player) => player.accent_color_0,
+            None => [1., 1., 1., 1.],
+        };
+
+        return InstanceRaw { 
+            position: [(projectile.pos.x - cam_pos.x) as f32, (projectile.pos.y - cam_pos.y) as f32], 
+            color,
+        };
+    }
+}
@@ -0,0 +1,47 @@
+struct VertexInput {
+    @location(0) clip_position: vec2<f32>,
+    @location(1) tex_coords: vec2<f32>,
+}
+
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) tex_coords: vec2<f32>,
+    @location(1) color: vec4<f32>,
+}
+
+struct InstanceInput {
+    @location(2) position: vec2<f32>,
+    @location(3) color: vec4<f32>,
+}
+
+// A uniform that handles all the uniform data
+struct OverallUniform {
+    view_proj: mat4x4<f32>,
+}
+
+@group(0) @binding(0)
+var<uniform> overal_uniform: OverallUniform;
+
+@vertex
+fn vs_main(
+    model: VertexInput,
+    instance: InstanceInput,
+) -> VertexOutput {
+    var out: VertexOutput;
+    out.tex_coords = model.tex_coords;
+    out.clip_position = overal_uniform.view_proj * vec4<f32>(model.clip_position + instance.position, 0., 1.);
+    out.color = instance.color;
+
+    return out;
+}
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    // Rounds the quad into a dot
+    let from_center = in.tex_coords * 2. - vec2<f32>(1., 1.);
+    if (dot(from_center, from_center) > 1.) {
+        discard;
+    }
+
+    return in.color;
+}
@@ -0,0 +1,475 @@
+use std::net::{SocketAddr, UdpSocket};
+use std::time::{Duration, Instant};
+
+use cgmath::{Point2, Vector2};
+
+use game_logic::{World, WorldConfig, Player, PlayerInput, Asteroid, AsteroidSize, Projectile, EntityId};
+use server::interface::Server;
+use web_types::{UpMsgBox, DownMsgBox, GameUpdate, PlayerLook, WorldHash, HASH_INTERVAL_TICKS, ConnectionState, DisconnectReason, Timestamp, MAX_PACKET_SIZE, TIMEOUT};
+
+// The client is a binary, its network module is built on its own
+#[allow(dead_code)]
+#[path = "../src/network.rs"]
+mod network;
+
+use network::NetworkClient;
+
+const SEED: u64 = 77;
+
+/// Runs a server in the background, on a port picked by the system
+fn start_server() -> SocketAddr {
+    let mut world = World::new(3, 3, SEED, WorldConfig::new());
+    for id in world.player_ids() {
+        world.remove_player(id);
+    }
+
+    let mut server = Server::bind(([127, 0, 0, 1], 0).into(), world).unwrap();
+    let addr = server.local_addr().unwrap();
+    std::thread::spawn(move || server.run(None));
+
+    return addr;
+}
+
+/// A local world like the one of the client before it connects, with its own sprites and seed
+fn local_world() -> World {
+    return World::new(5, 5, 1, WorldConfig::new());
+}
+
+/// Calls `f` until it is true, or panics after 2 seconds
+fn wait_until<F>(mut f: F) where F: FnMut() -> bool {
+    let deadline = Instant::now() + Duration::from_secs(2);
+
+    while !f() {
+        assert!(Instant::now() < deadline, "timed out");
+        std::thread::sleep(Duration::from_millis(1));
+    }
+}
+
+/// Keeps the client running for `duration`
+fn run_for(client: &mut NetworkClient, world: &mut World, duration: Duration) {
+    let end = Instant::now() + duration;
+
+    while Instant::now() < end {
+        client.update(world);
+        std::thread::sleep(Duration::from_millis(1));
+    }
+}
+
+fn connect(addr: SocketAddr, world: &mut World) -> NetworkClient {
+    let mut client = NetworkClient::connect(addr, PlayerLook::default()).unwrap();
+    wait_until(|| client.update(world) == ConnectionState::Connected);
+
+    return client;
+}
+
+/// Plays the server by hand, to send what a real one wouldn't in that order
+struct FakeServer {
+    socket: UdpSocket,
+    client: Option<SocketAddr>,
+}
+
+impl FakeServer {
+    fn new() -> FakeServer {
+        let socket = UdpSocket::bind("127.0.0.1:0").unwrap();
+        socket.set_nonblocking(true).unwrap();
+
+        return FakeServer { socket, client: None };
+    }
+
+    fn addr(&self) -> SocketAddr {
+        return self.socket.local_addr().unwrap();
+    }
+
+    /// Everything the client sent since the last call
+    fn received(&mut self) -> Vec<UpMsgBox> {
+        let mut buf = [0; MAX_PACKET_SIZE];
+        let mut msgs = Vec::new();
+
+        while let Ok((len, addr)) = self.socket.recv_from(&mut buf) {
+            self.client = Some(addr);
+            msgs.push(UpMsgBox::from_bytes(&buf[..len]).unwrap());
+        }
+
+        return msgs;
+    }
+
+    fn send(&self, msg: DownMsgBox) {
+        self.socket.send_to(&msg.to_bytes(), self.client.unwrap()).unwrap();
+    }
+
+    /// Accepts the client as the player `id`
+    fn accept(&mut self, id: EntityId) -> (NetworkClient, World) {
+        let mut world = local_world();
+        let mut client = NetworkClient::connect(self.addr(), PlayerLook::default()).unwrap();
+
+        wait_until(|| self.received().iter().any(|msg| matches!(msg, UpMsgBox::NewConnection { .. })));
+        self.send(DownMsgBox::ConnectionAcknowleged {
+            key: 1,
+            your_id: id,
+            world_seed: SEED,
+            world_config: WorldConfig::new(),
+            server_time: Timestamp::ZERO,
+        });
+        wait_until(|| client.update(&mut world) == ConnectionState::Connected);
+
+        return (client, world);
+    }
+
+    fn update_player(&self, id: EntityId, x: f64, tick: u64) {
+        let mut player = Player::new();
+        player.pos.x = x;
+        self.send_player(id, player, tick);
+    }
+
+    fn send_player(&self, id: EntityId, player: Player, tick: u64) {
+        self.send(DownMsgBox::GameUpdate(GameUpdate::PlayerUpdate { id, player, time: Timestamp::of_tick(tick) }));
+    }
+
+    fn send_chunk(&self, chunk: (i64, i64), tick: u64, asteroids: &[Asteroid]) {
+        for update in GameUpdate::chunk_state(chunk, Timestamp::of_tick(tick), asteroids) {
+            self.send(DownMsgBox::GameUpdate(update));
+        }
+    }
+}
+
+/// An asteroid of the server, in the chunk at the origin
+fn server_asteroid(n: u32) -> Asteroid {
+    return Asteroid {
+        id: EntityId::from_parts(100 + n, 0),
+        pos: Point2 { x: 0.01 * n as f64, y: 0.5 },
+        vel: Vector2 { x: 0., y: 0. },
+        rot_speed: 0.,
+        rot: 0.,
+        img_idx: 7,
+        spawn_time: 0.,
+        size: AsteroidSize::Medium,
+    };
+}
+
+fn positions(world: &World) -> Vec<(f64, f64)> {
+    let mut positions: Vec<_> = world.asteroids.iter().map(|asteroid| (asteroid.pos.x, asteroid.pos.y)).collect();
+    positions.sort_by(|a, b| a.partial_cmp(b).unwrap());
+
+    return positions;
+}
+
+fn remote_players(client: &NetworkClient, world: &World) -> Vec<Player> {
+    return world.players.iter().filter(|player| player.id != client.player()).copied().collect();
+}
+
+#[test]
+fn handshake_takes_the_world_of_the_server() {
+    let addr = start_server();
+    let mut world = local_world();
+    let mut client = connect(addr, &mut world);
+
+    assert_eq!(world.seed(), SEED);
+    assert_eq!(world.player_ids(), vec![client.player()]);
+    assert!(!world.has_authority());
+
+    // The asteroids are the ones of the server, not generated locally
+    wait_until(|| {
+        client.update(&mut world);
+        !world.asteroids.is_empty()
+    });
+    assert!(world.asteroids.iter().all(|asteroid| asteroid.img_idx < 5));
+
+    client.disconnect(&mut world);
+    assert!(world.has_authority());
+}
+
+#[test]
+fn clients_see_each_other_join_and_leave() {
+    let addr = start_server();
+    let mut first_world = local_world();
+    let mut second_world = local_world();
+    let mut first = connect(addr, &mut first_world);
+    let mut second = connect(addr, &mut second_world);
+
+    wait_until(|| {
+        first.update(&mut first_world);
+        second.update(&mut second_world);
+        first_world.players.len() == 2 && second_world.players.len() == 2
+    });
+
+    second.disconnect(&mut second_world);
+    assert_eq!(second_world.player_ids(), vec![second.player()]);
+
+    wait_until(|| {
+        first.update(&mut first_world);
+        first_world.players.len() == 1
+    });
+    assert_eq!(first_world.player_ids(), vec![first.player()]);
+}
+
+#[test]
+fn server_ids_are_mapped_to_local_ones() {
+    let mut server = FakeServer::new();
+    let (mut client, mut world) = server.accept(EntityId::from_parts(3, 0));
+    let other = EntityId::from_parts(1, 0);
+
+    // Its arrival was lost, the update is enough
+    server.update_player(other, 5., 10);
+    wait_until(|| {
+        client.update(&mut world);
+        remote_players(&client, &world).len() == 1
+    });
+
+    let remote = remote_players(&client, &world)[0];
+    assert_ne!(remote.id, other);
+    assert_eq!(remote.pos.x, 5.);
+}
+
+#[test]
+fn late_updates_are_ignored() {
+    let mut server = FakeServer::new();
+    let (mut client, mut world) = server.accept(EntityId::from_parts(3, 0));
+    let other = EntityId::from_parts(1, 0);
+
+    server.update_player(other, 5., 10);
+    server.update_player(other, -5., 9);
+    run_for(&mut client, &mut world, Duration::from_millis(100));
+
+    assert_eq!(remote_players(&client, &world)[0].pos.x, 5.);
+}
+
+#[test]
+fn updates_after_a_disconnection_do_not_bring_the_player_back() {
+    let mut server = FakeServer::new();
+    let (mut client, mut world) = server.accept(EntityId::from_parts(3, 0));
+    let other = EntityId::from_parts(1, 0);
+
+    server.send(DownMsgBox::GameUpdate(GameUpdate::NewPlayer { id: other, player: Player::new() }));
+    wait_until(|| {
+        client.update(&mut world);
+        remote_players(&client, &world).len() == 1
+    });
+
+    // Sent in the same tick, or reordered
+    server.send(DownMsgBox::GameUpdate(GameUpdate::PlayerDisconnect { id: other }));
+    server.update_player(other, 5., 20);
+    run_for(&mut client, &mut world, Duration::from_millis(100));
+
+    assert!(remote_players(&client, &world).is_empty());
+}
+
+#[test]
+fn silent_players_are_dropped() {
+    let mut server = FakeServer::new();
+    let id = EntityId::from_parts(3, 0);
+    let (mut client, mut world) = server.accept(id);
+    let other = EntityId::from_parts(1, 0);
+
+    server.update_player(other, 5., 1);
+    wait_until(|| {
+        client.update(&mut world);
+        remote_players(&client, &world).len() == 1
+    });
+
+    // Its disconnection was lost, only the local player is still updated
+    let mut tick = 2;
+    wait_until(|| {
+        server.update_player(id, 0., tick);
+        tick += 1;
+        client.update(&mut world);
+        remote_players(&client, &world).is_empty()
+    });
+    assert_eq!(client.update(&mut world), ConnectionState::Connected);
+}
+
+#[test]
+fn the_input_is_sent_again_without_changes() {
+    let mut server = FakeServer::new();
+    let (mut client, mut world) = server.accept(EntityId::from_parts(3, 0));
+
+    let mut input = game_logic::PlayerInput::new(world.tick() + 1, client.player());
+    input.fire = true;
+    world.queue_input(input);
+    world.step();
+    server.received();
+
+    // Any of them can be lost, the server gets the input with the next one
+    let mut n_inputs = 0;
+    wait_until(|| {
+        client.update(&mut world);
+        n_inputs += server.received().iter().filter(|msg| matches!(msg, UpMsgBox::Input { input } if input.fire)).count();
+        n_inputs >= 3
+    });
+}
+
+#[test]
+fn clients_hash_the_players_the_server_sent() {
+    let mut server = FakeServer::new();
+    let id = EntityId::from_parts(3, 0);
+    let (mut client, mut world) = server.accept(id);
+    let other = EntityId::from_parts(1, 0);
+
+    let mut hurt = Player::new();
+    hurt.health = 40.;
+    let tick = HASH_INTERVAL_TICKS;
+
+    // Not every player got the hashed tick yet
+    server.send_player(other, hurt, tick);
+    run_for(&mut client, &mut world, Duration::from_millis(50));
+    assert!(!server.received().iter().any(|msg| matches!(msg, UpMsgBox::WorldHash(_))));
+
+    server.send_player(id, Player::new(), tick);
+    let expected = WorldHash::of_players(tick, [(other, &hurt), (id, &Player::new())]);
+    wait_until(|| {
+        client.update(&mut world);
+        server.received().iter().any(|msg| matches!(msg, UpMsgBox::WorldHash(hash) if *hash == expected))
+    });
+}
+
+#[test]
+fn unrecognised_clients_play_locally() {
+    let mut server = FakeServer::new();
+    let (mut client, mut world) = server.accept(EntityId::from_parts(3, 0));
+
+    server.update_player(EntityId::from_parts(1, 0), 5., 1);
+    server.send(DownMsgBox::Unrecognised);
+
+    wait_until(|| client.update(&mut world) == ConnectionState::Disconnected(DisconnectReason::Refused));
+    assert_eq!(world.player_ids(), vec![client.player()]);
+}
+
+#[test]
+fn closing_servers_leave_the_client_playing_locally() {
+    let mut server = FakeServer::new();
+    let (mut client, mut world) = server.accept(EntityId::from_parts(3, 0));
+
+    server.update_player(EntityId::from_parts(1, 0), 5., 1);
+    server.send(DownMsgBox::ServerClosing);
+
+    wait_until(|| client.update(&mut world) == ConnectionState::Disconnected(DisconnectReason::Closed));
+    assert_eq!(world.player_ids(), vec![client.player()]);
+}
+
+#[test]
+fn silent_servers_time_out() {
+    let mut server = FakeServer::new();
+    let (mut client, mut world) = server.accept(EntityId::from_parts(3, 0));
+
+    run_for(&mut client, &mut world, TIMEOUT + Duration::from_millis(100));
+
+    assert_eq!(client.update(&mut world), ConnectionState::Disconnected(DisconnectReason::TimedOut));
+    assert_eq!(world.player_ids(), vec![client.player()]);
+}
+
+#[test]
+fn forged_keep_alives_are_ignored() {
+    let mut server = FakeServer::new();
+    let (mut client, mut world) = server.accept(EntityId::from_parts(3, 0));
+
+    server.send(DownMsgBox::KeepAlive { time: Timestamp::from_micros(u64::MAX), server_time: Timestamp::from_micros(u64::MAX) });
+    server.send(DownMsgBox::KeepAlive { time: Timestamp::ZERO, server_time: Timestamp::from_micros(i64::MAX as u64 + 1) });
+    run_for(&mut client, &mut world, Duration::from_millis(100));
+
+    assert_eq!(client.update(&mut world), ConnectionState::Connected);
+    assert!(client.rtt().is_none_or(|rtt| rtt < Duration::from_secs(1)));
+}
+
+#[test]
+fn chunk_snapshots_replace_the_asteroids() {
+    let mut server = FakeServer::new();
+    let (mut client, mut world) = server.accept(EntityId::from_parts(3, 0));
+    assert!(world.asteroids.is_empty());
+
+    server.send_chunk((0, 0), 5, &[server_asteroid(1), server_asteroid(2)]);
+    wait_until(|| {
+        client.update(&mut world);
+        world.asteroids.len() == 2
+    });
+    // The server has more sprites
+    assert!(world.asteroids.iter().all(|asteroid| asteroid.img_idx == 7 % 5));
+
+    // The second asteroid was destroyed on the server, the first moved
+    let moved = Asteroid { pos: Point2 { x: 0.25, y: 0.25 }, ..server_asteroid(1) };
+    server.send_chunk((0, 0), 6, &[moved]);
+    wait_until(|| {
+        client.update(&mut world);
+        world.asteroids.len() == 1
+    });
+    assert_eq!(positions(&world), vec![(0.25, 0.25)]);
+
+    // A late snapshot doesn't bring it back
+    server.send_chunk((0, 0), 4, &[server_asteroid(1), server_asteroid(2), server_asteroid(3)]);
+    run_for(&mut client, &mut world, Duration::from_millis(50));
+    assert_eq!(positions(&world), vec![(0.25, 0.25)]);
+}
+
+#[test]
+fn split_snapshots_apply_once_complete() {
+    let mut server = FakeServer::new();
+    let (mut client, mut world) = server.accept(EntityId::from_parts(3, 0));
+
+    let asteroids: Vec<_> = (0..40).map(server_asteroid).collect();
+    let parts = GameUpdate::chunk_state((0, 0), Timestamp::of_tick(5), &asteroids);
+    assert_eq!(parts.len(), 2);
+
+    server.send(DownMsgBox::GameUpdate(parts[1].clone()));
+    run_for(&mut client, &mut world, Duration::from_millis(50));
+    assert!(world.asteroids.is_empty());
+
+    server.send(DownMsgBox::GameUpdate(parts[0].clone()));
+    wait_until(|| {
+        client.update(&mut world);
+        world.asteroids.len() == 40
+    });
+}
+
+#[test]
+fn remote_projectiles_are_simulated() {
+    let mut server = FakeServer::new();
+    let id = EntityId::from_parts(3, 0);
+    let (mut client, mut world) = server.accept(id);
+    let other = EntityId::from_parts(1, 0);
+
+    server.update_player(other, 5., 1);
+    wait_until(|| {
+        client.update(&mut world);
+        remote_players(&client, &world).len() == 1
+    });
+
+    let projectile = |owner| Projectile {
+        id: EntityId::from_parts(50, 0),
+        pos: Point2 { x: 5., y: 0. },
+        vel: Vector2 { x: 1., y: 0. },
+        owner,
+        expire_time: Duration::from_millis(800),
+    };
+
+    // The local player fired its own already
+    server.send(DownMsgBox::GameUpdate(GameUpdate::NewProjectile { projectile: projectile(id) }));
+    run_for(&mut client, &mut world, Duration::from_millis(50));
+    assert!(world.projectiles.is_empty());
+
+    server.send(DownMsgBox::GameUpdate(GameUpdate::NewProjectile { projectile: projectile(other) }));
+    wait_until(|| {
+        client.update(&mut world);
+        world.projectiles.len() == 1
+    });
+    let local = world.projectiles[0];
+    assert_eq!(local.owner, remote_players(&client, &world)[0].id);
+    assert_eq!(local.expire_time, world.time() + Duration::from_millis(800));
+}
+
+#[test]
+fn clients_see_the_projectiles_of_the_others() {
+    let addr = start_server();
+    let mut shooter_world = local_world();
+    let mut watcher_world = local_world();
+    let mut shooter = connect(addr, &mut shooter_world);
+    let mut watcher = connect(addr, &mut watcher_world);
+
+    let mut input = PlayerInput::new(shooter_world.tick() + 1, shooter.player());
+    input.fire = true;
+    shooter_world.queue_input(input);
+    shooter_world.step();
+
+    wait_until(|| {
+        shooter.update(&mut shooter_world);
+        watcher.update(&mut watcher_world);
+        watcher_world.projectiles.iter().any(|projectile| projectile.owner != watcher.player() && !projectile.owner.is_none())
+    });
+}
@@ -0,0 +1,155 @@
+//! Benchmarks of the simulation, headless so they run anywhere: `cargo bench -p game_logic`
+//! The player counts show how the streaming of the asteroids scales
+
+use std::time::Duration;
+
+use cgmath::Point2;
+use criterion::{criterion_group, criterion_main, BatchSize, BenchmarkId, Criterion};
+
+use game_logic::{World, WorldConfig, Player, ManualClock, WORLD_UPD_RATE, Asteroid};
+use game_logic::asteroids::{self, AsteroidManager};
+
+const PLAYER_COUNTS: [usize; 3] = [1, 10, 100];
+/// Far enough that the players never share chunks, so every player streams its own area
+const PLAYER_SPACING: f64 = 30.;
+const SEED: u64 = 42;
+
+/// Spreads the players on a square grid
+fn player_positions(n_players: usize) -> Vec<Point2<f64>> {
+    let side = (n_players as f64).sqrt().ceil() as usize;
+
+    return (0..n_players).map(|idx| Point2 { 
+        x: (idx % side) as f64 * PLAYER_SPACING, 
+        y: (idx / side) as f64 * PLAYER_SPACING,
+    }).collect();
+}
+
+/// A world with `n_players` spread across space, stepped until every chunk around them is loaded
+/// so the updates measure the steady state and not the streaming in
+fn spread_world(n_players: usize) -> World {
+    let mut world = World::new(3, 3, SEED, WorldConfig::new());
+
+    for id in world.player_ids() {
+        world.remove_player(id);
+    }
+    for pos in player_positions(n_players) {
+        let mut player = Player::new();
+        player.pos = pos;
+        world.add_player(player);
+    }
+
+    // The streaming loads some chunks every tick while any is missing
+    let mut n_chunks = None;
+    while n_chunks != Some(world.asteroid_manager().chunks().len()) {
+        n_chunks = Some(world.asteroid_manager().chunks().len());
+        world.step();
+    }
+
+    return world;
+}
+
+fn bench_update(c: &mut Criterion) {
+    let mut group = c.benchmark_group("world_update");
+    // The big worlds hold hundreds of thousands of asteroids, a few samples are enough
+    group.sample_size(10);
+
+    for n_players in PLAYER_COUNTS {
+        let mut world = spread_world(n_players);
+        let clock = ManualClock::new();
+        world.set_clock(Box::new(clock.clone()));
+
+        group.bench_with_input(BenchmarkId::from_parameter(n_players), &n_players, |b, _| {
+            b.iter(|| {
+                clock.advance(WORLD_UPD_RATE);
+                world.update();
+            });
+        });
+    }
+
+    group.finish();
+}
+
+/// No streaming budget, so a single call loads or unloads everything there is to
+fn unbudgeted_config() -> WorldConfig {
+    return WorldConfig { chunks_per_tick: usize::MAX, ..WorldConfig::new() };
+}
+
+fn bench_load_chunks(c: &mut Criterion) {
+    let mut group = c.benchmark_group("load_chunks");
+    group.sample_size(10);
+
+    for n_players in PLAYER_COUNTS {
+        let world = spread_world(n_players);
+
+        // From an empty manager, so every chunk around every player gets generated
+        group.bench_with_input(BenchmarkId::from_parameter(n_players), &n_players, |b, _| {
+            b.iter_batched(
+                || (AsteroidManager::new(unbudgeted_config()), Vec::new()), 
+                |(mut manager, mut asteroids)| {
+                    manager.load_chunks(&mut asteroids, &world.players, Duration::ZERO, 3, SEED);
+                    return asteroids;
+                }, 
+                BatchSize::LargeInput,
+            );
+        });
+    }
+
+    group.finish();
+}
+
+fn bench_unload_chunks(c: &mut Criterion) {
+    let mut group = c.benchmark_group("unload_chunks");
+    group.sample_size(10);
+
+    for n_players in PLAYER_COUNTS {
+        let world = spread_world(n_players);
+
+        let config = unbudgeted_config();
+        let mut manager = AsteroidManager::new(config);
+        let mut asteroids: Vec<Asteroid> = Vec::new();
+        manager.load_chunks(&mut asteroids, &world.players, Duration::ZERO, 3, SEED);
+        manager.rebuild_index(&asteroids);
+
+        // Every player moved past the gap between the rings, so the trailing edge of each area gets unloaded
+        let mut moved = world.players.clone();
+        for player in &mut moved {
+            player.pos.x += (config.chunk_unload_dist - config.chunk_load_dist + 2.) * config.chunk_size;
+        }
+
+        group.bench_with_input(BenchmarkId::from_parameter(n_players), &n_players, |b, _| {
+            b.iter_batched(
+                || (AsteroidManager::from_chunks(&manager.chunks(), config), asteroids.clone()),
+                |(mut manager, mut asteroids)| {
+                    manager.rebuild_index(&asteroids);
+                    manager.unload_chunks(&mut asteroids, &moved);
+                    return asteroids;
+                },
+                BatchSize::LargeInput,
+            );
+        });
+    }
+
+    group.finish();
+}
+
+fn bench_chunk_generation(c: &mut Criterion) {
+    let mut group = c.benchmark_group("spawn_ast_in_chunk");
+
+    for n_ast in [1, 10, 100] {
+        group.bench_with_input(BenchmarkId::from_parameter(n_ast), &n_ast, |b, n_ast| {
+            let mut chunk = 0;
+
+            b.iter(|| {
+                let mut asteroids = Vec::with_capacity(*n_ast);
+                chunk += 1;
+                asteroids::spawn_ast_in_chunk(&mut asteroids, 3, *n_ast, (chunk, -chunk), 0., SEED, &WorldConfig::new());
+                return asteroids;
+            });
+        });
+    }
+
+    group.finish();
+}
+
+criterion_group!(benches, bench_update, bench_load_chunks, bench_unload_chunks, bench_chunk_generation);
+criterion_main!(benches);
@@ -1,19 +1,81 @@
-use cgmath::{Point2, Vector2};
-use rand::Rng;
+use cgmath::{Point2, Vector2, MetricSpace};
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+use serde::{Serialize, Deserialize};
 
-use super::{World, Player};
+use super::{World, Player, EntityId, WorldConfig, collision};
+use super::chunk_store::{ChunkStore, StoredChunk};
+use super::density::DensityModel;
+use super::spatial::SpatialIndex;
 
 use std::f32::consts::PI;
+use std::time::Duration;
 use fnv::FnvHashMap as HashMap;
 
-#[derive(Debug, Clone, Copy)]
+/// The size tier of an asteroid, destroying one splits it into asteroids of the tier below
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum AsteroidSize {
+    Small,
+    Medium,
+    Large,
+}
+
+impl AsteroidSize {
+    /// The scale of the asteroid compared to a medium one
+    pub fn scale(&self) -> f64 {
+        return match self {
+            AsteroidSize::Small => 0.5,
+            AsteroidSize::Medium => 1.,
+            AsteroidSize::Large => 2.,
+        };
+    }
+
+    /// The size of the fragments, none for the smallest asteroids
+    pub fn smaller(&self) -> Option<AsteroidSize> {
+        return match self {
+            AsteroidSize::Small => None,
+            AsteroidSize::Medium => Some(AsteroidSize::Small),
+            AsteroidSize::Large => Some(AsteroidSize::Medium),
+        };
+    }
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
 pub struct Asteroid {
+    /// Given by the world when the asteroid is added to it
+    pub id: EntityId,
     pub pos: Point2<f64>,
     pub vel: Vector2<f64>,
     pub rot_speed: f32,
     pub rot: f32,
     pub img_idx: i32,
     pub spawn_time: f32,
+    pub size: AsteroidSize,
+}
+
+impl Asteroid {
+    /// The radius of the hitbox of a medium asteroid
+    pub(crate) const RADIUS: f64 = 0.02;
+    /// The radius of the biggest asteroid, to know how far to look around a point
+    pub(crate) const MAX_RADIUS: f64 = Self::RADIUS * 2.;
+
+    /// Mass per unit of area, only the ratios between masses matter
+    const DENSITY: f64 = 1.;
+
+    pub fn radius(&self) -> f64 {
+        return Self::RADIUS * self.size.scale();
+    }
+
+    pub fn mass(&self) -> f64 {
+        let radius = self.radius();
+        return Self::DENSITY * std::f64::consts::PI * radius * radius;
+    }
+
+    /// The moment of inertia of the asteroid, taken as a uniform disc
+    pub fn inertia(&self) -> f64 {
+        let radius = self.radius();
+        return 0.5 * self.mass() * radius * radius;
+    }
 }
 
 pub fn update_asteroids(world: &mut World, delta_t: f64) {
@@ -24,36 +86,110 @@ pub fn update_asteroids(world: &mut World, delta_t: f64) {
 
         x.pos += x.vel * delta_t;
     }
+
+    world.asteroid_manager.update_index(&world.asteroids);
+
+    if collision::resolve_asteroid_collisions(&mut world.asteroids, world.asteroid_manager.index()) {
+        world.asteroid_manager.update_index(&world.asteroids);
+    }
 }
 
-const AST_SPEED_MAX: f64 = 0.1; // The extreme of what the random speed of an asteroid can be
-const AST_ROT_SPEED_MAX: f32 = 1.; // The extreme of what the random speed of rotation of an asteroid can be
+const N_FRAGMENTS: usize = 2; // The number of asteroids an asteroid splits into
+const FRAGMENT_SPREAD_SPEED: f64 = 0.15; // The max speed at which fragments move away from each other
+
+/// Breaks an asteroid into smaller ones, returns nothing for the smallest asteroids
+/// The fragments keep the velocity of their parent and are pushed away from each other
+/// in opposite directions, so the spread doesn't add momentum
+pub fn split_asteroid<R>(asteroid: &Asteroid, rng: &mut R, time: f32, config: &WorldConfig) -> Vec<Asteroid> where R: Rng {
+    let size = match asteroid.size.smaller() {
+        Some(size) => size,
+        None => return Vec::new(),
+    };
+
+    let angle = rng.gen_range(-std::f64::consts::PI..std::f64::consts::PI);
+    let speed = rng.gen_range(0.5..1.) * FRAGMENT_SPREAD_SPEED;
+    let offset_radius = Asteroid::RADIUS * size.scale();
+
+    let mut output = Vec::with_capacity(N_FRAGMENTS);
+
+    for x in 0..N_FRAGMENTS {
+        let angle = angle + x as f64 * std::f64::consts::TAU / N_FRAGMENTS as f64;
+        let dir = Vector2 { x: angle.cos(), y: angle.sin() };
+
+        output.push(Asteroid {
+            id: EntityId::NONE,
+            pos: asteroid.pos + dir * offset_radius,
+            vel: asteroid.vel + dir * speed,
+            rot_speed: asteroid.rot_speed + rng.gen_range(-config.ast_rot_speed_max..config.ast_rot_speed_max),
+            rot: asteroid.rot,
+            img_idx: asteroid.img_idx,
+            spawn_time: time,
+            size,
+        });
+    }
+
+    return output;
+}
+
+/// Removes the asteroids at `idxs`, sorted and without duplicates, and gives them back from the last index to the first
+pub(crate) fn remove_asteroids(asteroids: &mut Vec<Asteroid>, idxs: &[usize]) -> Vec<Asteroid> {
+    // From the end so that swap_remove never moves an asteroid that still has to be removed
+    return idxs.iter().rev().map(|idx| asteroids.swap_remove(*idx)).collect();
+}
+
+/// Picks the size of a newly generated asteroid, big ones are rarer
+fn random_size<R>(rng: &mut R) -> AsteroidSize where R: Rng {
+    return match rng.gen_range(0..5) {
+        0 | 1 => AsteroidSize::Small,
+        2 | 3 => AsteroidSize::Medium,
+        _ => AsteroidSize::Large,
+    };
+}
+
+/// Creates the random generator used to fill a chunk
+/// The same world seed and chunk always give the same generator, on any machine
+pub fn chunk_rng(world_seed: u64, chunk: (i64, i64)) -> ChaCha8Rng {
+    let seed = splitmix64(splitmix64(world_seed ^ chunk.0 as u64) ^ chunk.1 as u64);
+
+    return ChaCha8Rng::seed_from_u64(seed);
+}
 
-const CHUNK_SIZE: f64 = 2.;
-const CHUNK_PLAYER_DIST: i64 = 6;  /// The size of the chunks that will be checked around the playery
-const ASTEROID_DESCPAWN_DIST: f64 = CHUNK_PLAYER_DIST as f64 * CHUNK_SIZE;
+/// Mixes the bits of a u64 so that close inputs give unrelated outputs
+pub(crate) fn splitmix64(x: u64) -> u64 {
+    let mut z = x.wrapping_add(0x9E3779B97F4A7C15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58476D1CE4E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D049BB133111EB);
+
+    return z ^ (z >> 31);
+}
 
 /// Spawns a desired amount of asteroids in a desired chunk of space
-pub fn spawn_ast_in_chunk(asteroids: &mut Vec<Asteroid>, n_ast_img: i32, n: usize, chunk: (i64, i64), time: f32) {
-    let mut to_add = Vec::with_capacity(n as usize);
+/// The asteroids only depend on the arguments, so any process knowing the world seed can regenerate a chunk
+pub fn spawn_ast_in_chunk(asteroids: &mut Vec<Asteroid>, n_ast_img: i32, n: usize, chunk: (i64, i64), time: f32, world_seed: u64, config: &WorldConfig) {
+    let mut to_add = Vec::with_capacity(n);
 
-    let mut thread_rng = rand::thread_rng();
+    let mut rng = chunk_rng(world_seed, chunk);
+    // The sprites come from their own stream, so the gameplay is the same whatever the number of images
+    let mut img_rng = chunk_rng(splitmix64(world_seed), chunk);
 
-    let chunk = cgmath::Vector2 { x: chunk.0 as f64 * CHUNK_SIZE, y: chunk.1 as f64 * CHUNK_SIZE };
+    let chunk_size = config.chunk_size;
+    let chunk = cgmath::Vector2 { x: chunk.0 as f64 * chunk_size, y: chunk.1 as f64 * chunk_size };
 
     for _x in 0..n {
-        let pos = cgmath::Point2 { x: rand::random::<f64>() * CHUNK_SIZE, y: rand::random::<f64>() * CHUNK_SIZE } + chunk;
+        let pos = cgmath::Point2 { x: rng.gen::<f64>() * chunk_size, y: rng.gen::<f64>() * chunk_size } + chunk;
 
         let ast = Asteroid { 
+            id: EntityId::NONE,
             pos, 
             vel: cgmath::Vector2 { 
-                x: thread_rng.gen_range(-AST_SPEED_MAX..AST_SPEED_MAX), 
-                y: thread_rng.gen_range(-AST_SPEED_MAX..AST_SPEED_MAX) 
+                x: rng.gen_range(-config.ast_speed_max..config.ast_speed_max), 
+                y: rng.gen_range(-config.ast_speed_max..config.ast_speed_max) 
             }, 
-            rot_speed: thread_rng.gen_range(-AST_ROT_SPEED_MAX..AST_ROT_SPEED_MAX), 
-            rot: thread_rng.gen_range(-PI..PI), 
-            img_idx: thread_rng.gen_range(0..n_ast_img), 
+            rot_speed: rng.gen_range(-config.ast_rot_speed_max..config.ast_rot_speed_max), 
+            rot: rng.gen_range(-PI..PI), 
+            img_idx: img_rng.gen_range(0..n_ast_img), 
             spawn_time: time,
+            size: random_size(&mut rng),
         };
 
         to_add.push(ast);
@@ -63,126 +199,195 @@ pub fn spawn_ast_in_chunk(asteroids: &mut Vec<Asteroid>, n_ast_img: i32, n: usiz
 }
 
 pub struct AsteroidManager {
-    last_del_idx: usize,
+    density: Box<dyn DensityModel>,
     // Holds a rough estimate to how many asteroids there are in a chunk
     chunk_counter: HashMap<(i64, i64), usize>,
+    /// The chunks left by every player, given back when a player comes near them again
+    store: ChunkStore,
+    index: SpatialIndex,
+    config: WorldConfig,
+    /// The chunks loaded and unloaded since the last `take_changed_chunks`
+    changed_chunks: Vec<(i64, i64)>,
 }
 
 impl AsteroidManager {
-    pub fn new() -> AsteroidManager {
-        return AsteroidManager { last_del_idx: 0, chunk_counter: HashMap::default() };
+    pub fn new(config: WorldConfig) -> AsteroidManager {
+        return AsteroidManager::from_chunks(&[], config);
     }
 
-    pub fn add_asteroids(&mut self, asteroids: &mut Vec<Asteroid>, players: &Vec<Player>, time: f32, n_ast_img: i32) {
-        for player in players {
-            let pos = chunk_pos_from_pos(player.pos);
+    /// Loads the chunks coming in range of the players and unloads the ones left behind, within the budget of a tick
+    /// `now` is the simulated time, used as the spawn time of the new asteroids
+    pub fn stream(&mut self, asteroids: &mut Vec<Asteroid>, players: &[Player], now: Duration, n_ast_img: i32, world_seed: u64) {
+        // The asteroids moved since the last update, the chunks are unloaded with what is in them now
+        self.update_index(asteroids);
+        self.unload_chunks(asteroids, players);
+        self.load_chunks(asteroids, players, now, n_ast_img, world_seed);
+    }
+
+    /// Generates or restores the chunks closer than `chunk_load_dist` to a player, the closest first
+    /// At most `chunks_per_tick` chunks are loaded, the others wait for the next call
+    pub fn load_chunks(&mut self, asteroids: &mut Vec<Asteroid>, players: &[Player], now: Duration, n_ast_img: i32, world_seed: u64) {
+        let time = now.as_secs_f32();
+        let players_pos = self.players_pos(players);
+        let load_dist = self.config.chunk_load_dist;
+        let reach = load_dist.ceil() as i64;
+
+        let mut to_load = Vec::new();
 
-            for x in (pos.0 - CHUNK_PLAYER_DIST)..(pos.0 + CHUNK_PLAYER_DIST) {
-                for y in (pos.1 - CHUNK_PLAYER_DIST)..(pos.1 + CHUNK_PLAYER_DIST) {
-                    if let None = self.chunk_counter.get(&(x, y)) {  // So if the chunk hasn't been generated
-                        let n_ast_expected = get_n_ast_in_chunk((x, y));
-                        spawn_ast_in_chunk(asteroids, n_ast_img, n_ast_expected, (x, y), time);
+        for pos in &players_pos {
+            let center = (pos.x.floor() as i64, pos.y.floor() as i64);
 
-                        self.chunk_counter.insert((x, y), n_ast_expected);
+            for x in (center.0 - reach)..=(center.0 + reach) {
+                for y in (center.1 - reach)..=(center.1 + reach) {
+                    if self.chunk_counter.contains_key(&(x, y)) {
+                        continue;
+                    }
+
+                    let dist = chunk_dist((x, y), &players_pos);
+                    if dist <= load_dist {
+                        to_load.push((dist, (x, y)));
                     }
                 }
             }
         }
+
+        // A chunk near several players is found once per player, always with the same distance
+        to_load.sort_unstable_by(|a, b| a.0.total_cmp(&b.0).then(a.1.cmp(&b.1)));
+        to_load.dedup_by_key(|(_, chunk)| *chunk);
+        to_load.truncate(self.config.chunks_per_tick);
+
+        for (_, chunk) in to_load {
+            let n_ast_expected = (self.density.density(world_seed, chunk) * self.config.ast_density).round().max(0.) as usize;
+            let stored = self.store.take(chunk).unwrap_or_default();
+
+            // A chunk is only generated once, after that it is what the players left of it
+            if !stored.generated {
+                spawn_ast_in_chunk(asteroids, n_ast_img, n_ast_expected, chunk, time, world_seed, &self.config);
+            }
+            // Their ids were freed when they were stored, the world gives them new ones
+            asteroids.extend(stored.asteroids);
+
+            self.chunk_counter.insert(chunk, n_ast_expected);
+            self.changed_chunks.push(chunk);
+        }
     }
 
-    /// The max number of asteroids that can be checked by the cleaning
-    /// To prevent big CPU usage at high number of asteroids & players
-    const N_UPDATES_FRAMES: usize = 500;
+    /// Moves the chunks farther than `chunk_unload_dist` from every player to the chunk store, the farthest first
+    /// Asteroids that drifted out of the loaded chunks are stored the same way once they are that far
+    /// At most `chunks_per_tick` chunks are unloaded, the others wait for the next call
+    pub fn unload_chunks(&mut self, asteroids: &mut Vec<Asteroid>, players: &[Player]) {
+        let players_pos = self.players_pos(players);
+        // Never below the load distance, or chunks would be unloaded as soon as they are loaded
+        let unload_dist = self.config.chunk_unload_dist.max(self.config.chunk_load_dist);
 
-    pub fn clean_asteroids(&mut self, asteroids: &mut Vec<Asteroid>, players: &[Player]) {
-        let mut ast_to_dispose = Vec::new();
+        let mut to_unload: Vec<_> = self.chunk_counter.keys().chain(self.index.cells().map(|(cell, _)| cell))
+            .map(|chunk| (chunk_dist(*chunk, &players_pos), *chunk))
+            .filter(|(dist, _)| *dist > unload_dist)
+            .collect();
 
-        if asteroids.len() > Self::N_UPDATES_FRAMES {
-            for (idx, ast) in asteroids.iter().enumerate() {
-                let mut to_dispose = true;
+        to_unload.sort_unstable_by(|a, b| b.0.total_cmp(&a.0).then(a.1.cmp(&b.1)));
+        to_unload.dedup_by_key(|(_, chunk)| *chunk);
+        to_unload.truncate(self.config.chunks_per_tick);
 
-                for player in players {
-                    if player.pos.x - ast.pos.x < ASTEROID_DESCPAWN_DIST && player.pos.y - ast.pos.y < ASTEROID_DESCPAWN_DIST {
-                        to_dispose = false;
-                        break;
-                    }
-                }
+        let mut ast_to_dispose = Vec::new();
 
-                if to_dispose {
-                    ast_to_dispose.push(idx);
-                }
-            }
-        } else {
-            let mut n_updates_now = 0;
-
-            if asteroids.len() > 0 {
-                while n_updates_now < AsteroidManager::N_UPDATES_FRAMES {
-                    let idx = (n_updates_now + self.last_del_idx) % asteroids.len();
-        
-                    let ast = asteroids[idx];
-        
-                    let mut to_dispose = true;
-        
-                    for player in players {
-                        if player.pos.x - ast.pos.x < ASTEROID_DESCPAWN_DIST && player.pos.y - ast.pos.y < ASTEROID_DESCPAWN_DIST {
-                            to_dispose = false;
-                            break;
-                        }
-                    }
-    
-                    if to_dispose {
-                        ast_to_dispose.push(idx);
-                    }
-        
-                    n_updates_now += 1;
-                }
-            }
+        for (_, chunk) in to_unload {
+            let mut idxs = self.index.cell(chunk).to_vec();
+            idxs.sort_unstable();
 
-            self.last_del_idx += n_updates_now;
+            let state = StoredChunk {
+                asteroids: idxs.iter().map(|idx| asteroids[*idx]).collect(),
+                // Stored even when empty, so a chunk whose asteroids were all destroyed stays empty
+                generated: self.chunk_counter.remove(&chunk).is_some(),
+            };
+            self.store.store(chunk, state);
+            self.changed_chunks.push(chunk);
+
+            ast_to_dispose.append(&mut idxs);
         }
 
-        for idx in ast_to_dispose.iter().rev() {
-            asteroids.swap_remove(*idx);
+        if !ast_to_dispose.is_empty() {
+            ast_to_dispose.sort_unstable();
+
+            remove_asteroids(asteroids, &ast_to_dispose);
+            self.rebuild_index(asteroids);
         }
+    }
+
+    /// The generated chunks and their expected amount of asteroids, sorted so it is always the same for the same state
+    pub fn chunks(&self) -> Vec<((i64, i64), usize)> {
+        let mut output: Vec<_> = self.chunk_counter.iter().map(|(chunk, n)| (*chunk, *n)).collect();
+        output.sort_unstable();
+
+        return output;
+    }
+
+    /// Recreates a manager that already generated `chunks`, the index still has to be rebuilt
+    pub fn from_chunks(chunks: &[((i64, i64), usize)], config: WorldConfig) -> AsteroidManager {
+        return AsteroidManager {
+            density: config.density.build(),
+            chunk_counter: chunks.iter().copied().collect(),
+            store: ChunkStore::new(config.chunk_store_capacity),
+            index: SpatialIndex::new(config.chunk_size),
+            config,
+            changed_chunks: Vec::new(),
+        };
+    }
 
-        self.clean_chunks(players);
+    /// The chunks loaded and unloaded since the last call
+    pub fn take_changed_chunks(&mut self) -> Vec<(i64, i64)> {
+        return std::mem::take(&mut self.changed_chunks);
     }
 
-    /// To prevent big memory usage and cpu usage by removing chunks that are too far away
-    fn clean_chunks(&mut self, players: &[Player]) {
-        let players: Vec<_> = players.iter().map(|player |chunk_pos_from_pos(player.pos)).collect();
+    /// Keeps the spatial index in sync with the asteroids, to call after they moved or were added
+    pub fn update_index(&mut self, asteroids: &[Asteroid]) {
+        self.index.update(asteroids.iter().map(|ast| ast.pos));
+    }
 
-        let mut to_remove = Vec::new();
+    /// Indexes the asteroids from scratch, to call after asteroids were removed
+    pub fn rebuild_index(&mut self, asteroids: &[Asteroid]) {
+        self.index.rebuild(asteroids.iter().map(|ast| ast.pos));
+    }
 
-        for (key, _val) in &self.chunk_counter {
-            let mut task_to_remove = true;
-            
-            for pos in &players {
-                if key.0 - pos.0 < CHUNK_PLAYER_DIST && key.1 - pos.1 < CHUNK_PLAYER_DIST {
-                    task_to_remove = false;
-                    break;
-                }
-            }
-            
-            if task_to_remove {
-                to_remove.push(*key);
-            }
-        }
+    pub fn config(&self) -> &WorldConfig {
+        return &self.config;
+    }
 
-        for key in to_remove.iter().rev() {
-            self.chunk_counter.remove(key);
-        }
+    /// Replaces the density model of the config, for maps with their own distribution
+    /// Only affects the chunks generated from now on, and isn't part of the snapshots
+    pub fn set_density_model(&mut self, density: Box<dyn DensityModel>) {
+        self.density = density;
     }
-}
 
-pub fn chunk_pos_from_pos(pos: cgmath::Point2<f64>) -> (i64, i64) {
-    return ((pos.x / CHUNK_SIZE) as i64, (pos.y / CHUNK_SIZE) as i64);
+    pub fn density_model(&self) -> &dyn DensityModel {
+        return self.density.as_ref();
+    }
+
+    /// The unloaded chunks kept for when players come back
+    pub fn chunk_store(&self) -> &ChunkStore {
+        return &self.store;
+    }
+
+    pub fn chunk_store_mut(&mut self) -> &mut ChunkStore {
+        return &mut self.store;
+    }
+
+    /// The spatial index over the asteroids, the indices are the ones of `World::asteroids`
+    pub fn index(&self) -> &SpatialIndex {
+        return &self.index;
+    }
+
+    /// The positions of the players in chunks, the unit of the streaming distances
+    fn players_pos(&self, players: &[Player]) -> Vec<Point2<f64>> {
+        return players.iter().map(|player| player.pos / self.config.chunk_size).collect();
+    }
 }
 
-/// Returns the expected amount of asteroids in a given chunk
-fn get_n_ast_in_chunk(pos: (i64, i64)) -> usize {
-    let pos = (pos.0.abs() as usize, pos.1.abs() as usize);
-    return pos.0 + pos.1 ^ 2;
+/// The distance in chunks between the center of `chunk` and the closest of `players_pos`
+fn chunk_dist(chunk: (i64, i64), players_pos: &[Point2<f64>]) -> f64 {
+    let center = Point2 { x: chunk.0 as f64 + 0.5, y: chunk.1 as f64 + 0.5 };
+
+    return players_pos.iter().map(|pos| pos.distance(center)).fold(f64::INFINITY, f64::min);
 }
 
 pub fn get_n_asteroid_img() -> i32 {
@@ -0,0 +1,144 @@
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+use fnv::FnvHashMap as HashMap;
+use serde::{Serialize, Deserialize};
+
+use super::Asteroid;
+
+/// What was left in a chunk when every player went away
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct StoredChunk {
+    /// The asteroids that were in the chunk, the destroyed ones are gone for good
+    pub asteroids: Vec<Asteroid>,
+    /// If the chunk was generated before being stored, it must not be generated again
+    /// Asteroids can drift into a chunk that was never generated, then it is still generated on load
+    pub generated: bool,
+}
+
+/// Keeps the unloaded chunks, so the space looks the same when players come back
+/// The least recently stored chunks are dropped when the store is full, or written to disk if it has a directory
+#[derive(Debug, Clone)]
+pub struct ChunkStore {
+    chunks: HashMap<(i64, i64), (u64, StoredChunk)>,
+    /// The chunks by the time they were stored, the oldest first
+    order: BTreeMap<u64, (i64, i64)>,
+    /// Incremented at every store, to order the chunks
+    stamp: u64,
+    capacity: usize,
+    dir: Option<PathBuf>,
+}
+
+impl ChunkStore {
+    pub fn new(capacity: usize) -> ChunkStore {
+        return ChunkStore { chunks: HashMap::default(), order: BTreeMap::new(), stamp: 0, capacity, dir: None };
+    }
+
+    /// Chunks pushed out of the memory are written in `dir` instead of being forgotten
+    /// The chunks already in `dir` are deleted, they were left by another world and would be read as this one's
+    pub fn set_dir(&mut self, dir: Option<PathBuf>) {
+        if let Some(dir) = &dir {
+            if let Err(err) = std::fs::create_dir_all(dir) {
+                logger::error(2, format!("Unable to create the chunk store directory {:?}: {}", dir, err));
+            }
+
+            clear_dir(dir);
+        }
+
+        self.dir = dir;
+    }
+
+    pub fn len(&self) -> usize {
+        return self.chunks.len();
+    }
+
+    pub fn is_empty(&self) -> bool {
+        return self.chunks.is_empty();
+    }
+
+    /// Stores the state of a chunk, merged with what was already stored for it
+    pub fn store(&mut self, chunk: (i64, i64), mut state: StoredChunk) {
+        if let Some(mut previous) = self.take(chunk) {
+            previous.asteroids.append(&mut state.asteroids);
+            previous.generated |= state.generated;
+            state = previous;
+        }
+
+        self.stamp += 1;
+        self.chunks.insert(chunk, (self.stamp, state));
+        self.order.insert(self.stamp, chunk);
+
+        while self.chunks.len() > self.capacity {
+            self.evict_oldest();
+        }
+    }
+
+    /// Removes the state of a chunk from the store, looking on disk if it isn't in memory
+    pub fn take(&mut self, chunk: (i64, i64)) -> Option<StoredChunk> {
+        if let Some((stamp, state)) = self.chunks.remove(&chunk) {
+            self.order.remove(&stamp);
+            return Some(state);
+        }
+
+        let path = self.chunk_path(chunk)?;
+        let bytes = std::fs::read(&path).ok()?;
+        let _ = std::fs::remove_file(&path);
+
+        return match bincode::deserialize(&bytes) {
+            Ok(state) => Some(state),
+            Err(err) => {
+                logger::error(2, format!("Unable to read the stored chunk {:?}: {}", chunk, err));
+                None
+            },
+        };
+    }
+
+    /// The chunks in memory, the oldest first
+    pub fn chunks(&self) -> Vec<((i64, i64), StoredChunk)> {
+        return self.order.values().map(|chunk| (*chunk, self.chunks[chunk].1.clone())).collect();
+    }
+
+    fn evict_oldest(&mut self) {
+        let (stamp, chunk) = match self.order.iter().next() {
+            Some((stamp, chunk)) => (*stamp, *chunk),
+            None => return,
+        };
+
+        self.order.remove(&stamp);
+        let (_, state) = self.chunks.remove(&chunk).expect("The order and the chunks of the store are in sync");
+
+        if let Some(path) = self.chunk_path(chunk) {
+            let result = bincode::serialize(&state).map_err(|err| err.to_string())
+                .and_then(|bytes| std::fs::write(&path, bytes).map_err(|err| err.to_string()));
+
+            if let Err(err) = result {
+                logger::error(2, format!("Unable to write the stored chunk {:?}: {}", chunk, err));
+            }
+        }
+    }
+
+    fn chunk_path(&self, chunk: (i64, i64)) -> Option<PathBuf> {
+        return self.dir.as_ref().map(|dir| dir.join(format!("chunk_{}_{}.bin", chunk.0, chunk.1)));
+    }
+}
+
+/// Deletes the stored chunks in `dir`, the other files are left alone
+fn clear_dir(dir: &Path) {
+    let entries = match std::fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(err) => {
+            logger::error(2, format!("Unable to clear the chunk store directory {:?}: {}", dir, err));
+            return;
+        },
+    };
+
+    for path in entries.filter_map(|entry| entry.ok()).map(|entry| entry.path()) {
+        let name = path.file_name().and_then(|name| name.to_str()).unwrap_or_default();
+
+        if name.starts_with("chunk_") && name.ends_with(".bin") {
+            if let Err(err) = std::fs::remove_file(&path) {
+                logger::error(2, format!("Unable to delete the stored chunk {:?}: {}", path, err));
+            }
+        }
+    }
+}
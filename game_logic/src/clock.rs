@@ -0,0 +1,74 @@
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, Instant};
+
+/// A source of time for the world
+/// The world only reads it to know how many ticks to simulate, so swapping it
+/// changes how fast the simulation runs but never what it computes
+pub trait Clock: Send {
+    /// The time elapsed since the clock was started
+    fn now(&self) -> Duration;
+
+    /// The max number of ticks simulated by a single update, the time beyond it is dropped
+    /// None to always catch up, for clocks that can't fall behind
+    fn max_ticks_per_update(&self) -> Option<u32> {
+        return None;
+    }
+}
+
+/// Follows the system clock
+pub struct RealClock {
+    start: Instant,
+}
+
+impl RealClock {
+    /// To prevent the simulation from spiraling when a frame takes too long
+    const MAX_TICKS_PER_UPDATE: u32 = 10;
+
+    pub fn new() -> RealClock {
+        return RealClock { start: Instant::now() };
+    }
+}
+
+impl Default for RealClock {
+    fn default() -> Self {
+        return RealClock::new();
+    }
+}
+
+impl Clock for RealClock {
+    fn now(&self) -> Duration {
+        return self.start.elapsed();
+    }
+
+    fn max_ticks_per_update(&self) -> Option<u32> {
+        return Some(Self::MAX_TICKS_PER_UPDATE);
+    }
+}
+
+/// A clock that only moves when it is told to, for tests and headless simulations
+/// All the clones share the same time, so a handle can be kept to drive the one given to the world
+#[derive(Debug, Clone, Default)]
+pub struct ManualClock {
+    nanos: Arc<AtomicU64>,
+}
+
+impl ManualClock {
+    pub fn new() -> ManualClock {
+        return ManualClock { nanos: Arc::new(AtomicU64::new(0)) };
+    }
+
+    pub fn advance(&self, by: Duration) {
+        self.nanos.fetch_add(by.as_nanos() as u64, Ordering::SeqCst);
+    }
+
+    pub fn set(&self, now: Duration) {
+        self.nanos.store(now.as_nanos() as u64, Ordering::SeqCst);
+    }
+}
+
+impl Clock for ManualClock {
+    fn now(&self) -> Duration {
+        return Duration::from_nanos(self.nanos.load(Ordering::SeqCst));
+    }
+}
@@ -0,0 +1,176 @@
+use cgmath::{Point2, Vector2, InnerSpace};
+use serde::{Serialize, Deserialize};
+
+use super::{World, Asteroid, AsteroidSize, EntityId};
+use super::spatial::SpatialIndex;
+
+/// Where and how deep two circles overlap
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Contact {
+    /// Unit vector going from the first entity toward the second
+    pub normal: Vector2<f64>,
+    /// By how much the circles overlap
+    pub depth: f64,
+}
+
+/// Emitted when two entities overlap during a tick
+/// The entities are referenced by id, so the events stay valid after the entities are removed or moved
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum CollisionEvent {
+    PlayerAsteroid {
+        player: EntityId,
+        asteroid: EntityId,
+        /// The normal goes from the player toward the asteroid
+        contact: Contact,
+    },
+    /// The asteroid was destroyed by the hit, so only what it was is kept
+    ProjectileAsteroid {
+        /// The player that fired the projectile
+        owner: EntityId,
+        size: AsteroidSize,
+        pos: Point2<f64>,
+    },
+    ProjectilePlayer {
+        /// The player that fired the projectile
+        owner: EntityId,
+        player: EntityId,
+        /// If the hit destroyed the ship
+        killed: bool,
+    },
+}
+
+/// Returns the contact between two circles if they overlap
+pub fn circle_overlap(pos_a: Point2<f64>, radius_a: f64, pos_b: Point2<f64>, radius_b: f64) -> Option<Contact> {
+    let delta = pos_b - pos_a;
+    let dist2 = delta.magnitude2();
+    let radii = radius_a + radius_b;
+
+    if dist2 >= radii * radii {
+        return None;
+    }
+
+    let dist = dist2.sqrt();
+    let normal = if dist > f64::EPSILON {
+        delta / dist
+    } else {  // The centers are on top of each other, any direction will do
+        Vector2 { x: 1., y: 0. }
+    };
+
+    return Some(Contact { normal, depth: radii - dist });
+}
+
+/// Finds every living player overlapping an asteroid
+pub fn detect_collisions(world: &World) -> Vec<CollisionEvent> {
+    let mut events = Vec::new();
+
+    for player in &world.players {
+        if !player.is_alive() {
+            continue;
+        }
+
+        let nearby = world.asteroid_manager.index().query_radius(player.pos, player.radius() + Asteroid::MAX_RADIUS);
+
+        for ast_idx in nearby {
+            let ast = &world.asteroids[ast_idx];
+            if let Some(contact) = circle_overlap(player.pos, player.radius(), ast.pos, ast.radius()) {
+                events.push(CollisionEvent::PlayerAsteroid { player: player.id, asteroid: ast.id, contact });
+            }
+        }
+    }
+
+    return events;
+}
+
+/// How much the surfaces of two asteroids grip each other, turning sliding into spin
+const ASTEROID_FRICTION: f64 = 0.2;
+
+/// Makes the overlapping asteroids bounce off each other
+/// The index must be up to date with the asteroids, returns if any asteroid was moved
+pub fn resolve_asteroid_collisions(asteroids: &mut [Asteroid], index: &SpatialIndex) -> bool {
+    let mut any_collision = false;
+
+    for idx_a in 0..asteroids.len() {
+        let nearby = index.query_radius(asteroids[idx_a].pos, asteroids[idx_a].radius() + Asteroid::MAX_RADIUS);
+
+        for idx_b in nearby {
+            if idx_b <= idx_a {  // Each pair is only handled once
+                continue;
+            }
+
+            let (left, right) = asteroids.split_at_mut(idx_b);
+            let (a, b) = (&mut left[idx_a], &mut right[0]);
+
+            if let Some(contact) = circle_overlap(a.pos, a.radius(), b.pos, b.radius()) {
+                collide_asteroids(a, b, contact);
+                any_collision = true;
+            }
+        }
+    }
+
+    return any_collision;
+}
+
+/// Bounces two overlapping asteroids and pushes them apart
+pub fn collide_asteroids(a: &mut Asteroid, b: &mut Asteroid, contact: Contact) {
+    apply_collision_impulse(a, b, contact);
+
+    // Moves both asteroids out of each other, weighted so that the center of mass doesn't move
+    let inv_mass_a = 1. / a.mass();
+    let inv_mass_b = 1. / b.mass();
+    let correction = contact.normal * (contact.depth / (inv_mass_a + inv_mass_b));
+
+    a.pos -= correction * inv_mass_a;
+    b.pos += correction * inv_mass_b;
+}
+
+/// Changes the velocities and spins of two touching asteroids as a perfectly elastic bounce
+/// The impulses are equal and opposite and applied at the same point, so both the momentum
+/// and the angular momentum of the pair are conserved
+pub fn apply_collision_impulse(a: &mut Asteroid, b: &mut Asteroid, contact: Contact) {
+    let normal = contact.normal;
+    let tangent = Vector2 { x: -normal.y, y: normal.x };
+
+    // The contact point, halfway through the overlap, relative to each center
+    let r_a = normal * (a.radius() - contact.depth * 0.5);
+    let r_b = -normal * (b.radius() - contact.depth * 0.5);
+
+    let vel_a = a.vel + cross_scalar(a.rot_speed as f64, r_a);
+    let vel_b = b.vel + cross_scalar(b.rot_speed as f64, r_b);
+    let rel_vel = vel_b - vel_a;
+
+    let vel_along_normal = rel_vel.dot(normal);
+    if vel_along_normal >= 0. {  // Already moving apart
+        return;
+    }
+
+    let inv_mass_a = 1. / a.mass();
+    let inv_mass_b = 1. / b.mass();
+    let inv_inertia_a = 1. / a.inertia();
+    let inv_inertia_b = 1. / b.inertia();
+
+    // The normal goes through both centers so it can't create any spin
+    let normal_impulse = -2. * vel_along_normal / (inv_mass_a + inv_mass_b);
+
+    let r_a_t = cross(r_a, tangent);
+    let r_b_t = cross(r_b, tangent);
+    let tangent_mass = inv_mass_a + inv_mass_b + r_a_t * r_a_t * inv_inertia_a + r_b_t * r_b_t * inv_inertia_b;
+    let max_friction = ASTEROID_FRICTION * normal_impulse;
+    let tangent_impulse = (-rel_vel.dot(tangent) / tangent_mass).clamp(-max_friction, max_friction);
+
+    let impulse = normal * normal_impulse + tangent * tangent_impulse;
+
+    a.vel -= impulse * inv_mass_a;
+    a.rot_speed -= (cross(r_a, impulse) * inv_inertia_a) as f32;
+    b.vel += impulse * inv_mass_b;
+    b.rot_speed += (cross(r_b, impulse) * inv_inertia_b) as f32;
+}
+
+/// The z component of the cross product of two 2D vectors
+fn cross(a: Vector2<f64>, b: Vector2<f64>) -> f64 {
+    return a.x * b.y - a.y * b.x;
+}
+
+/// The velocity of a point at `r` from the center of something spinning at `angular_vel`
+fn cross_scalar(angular_vel: f64, r: Vector2<f64>) -> Vector2<f64> {
+    return Vector2 { x: -angular_vel * r.y, y: angular_vel * r.x };
+}
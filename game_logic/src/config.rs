@@ -0,0 +1,164 @@
+use std::fmt;
+use std::path::Path;
+
+use serde::{Serialize, Deserialize};
+
+use super::density::DensityConfig;
+
+/// The farthest a chunk can be loaded or kept, in chunks, the chunks in range are scanned every tick
+pub const MAX_CHUNK_DIST: f64 = 64.;
+/// The most layers the density noise can have, each one is computed for every generated chunk
+pub const MAX_OCTAVES: u32 = 16;
+
+/// The tunable parameters of the generation and the simulation of a world
+/// Loaded from a json file so they can be tuned without recompiling, the missing fields take their default value
+/// Every process simulating the same world must use the same config, the server sends its own to the clients
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct WorldConfig {
+    /// The extreme of what the random speed of an asteroid can be
+    pub ast_speed_max: f64,
+    /// The extreme of what the random speed of rotation of an asteroid can be
+    pub ast_rot_speed_max: f32,
+    /// How the asteroids are distributed over the chunks
+    pub density: DensityConfig,
+    /// Multiplies the expected amount of asteroids in every chunk
+    pub ast_density: f64,
+    /// The side of a chunk, the unit of asteroid generation and of the spatial index
+    pub chunk_size: f64,
+    /// The chunks closer than this to a player are loaded, in chunks from the player to their center
+    pub chunk_load_dist: f64,
+    /// The chunks farther than this from every player are unloaded, in chunks
+    /// Kept above the load distance, so a player going back and forth over the edge doesn't reload the same chunks
+    pub chunk_unload_dist: f64,
+    /// The max number of chunks loaded and of chunks unloaded in a tick, spreads the streaming of a fast player over several ticks
+    pub chunks_per_tick: usize,
+    /// How many unloaded chunks are remembered, the oldest are forgotten past it
+    pub chunk_store_capacity: usize,
+    /// In radians per second
+    pub player_rot_speed: f32,
+}
+
+impl WorldConfig {
+    pub fn new() -> WorldConfig {
+        return WorldConfig {
+            ast_speed_max: 0.1,
+            ast_rot_speed_max: 1.,
+            density: DensityConfig::default(),
+            ast_density: 1.,
+            chunk_size: 2.,
+            chunk_load_dist: 6.,
+            chunk_unload_dist: 8.,
+            chunks_per_tick: 32,
+            chunk_store_capacity: 4096,
+            player_rot_speed: 4.5,
+        };
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> Result<WorldConfig, ConfigError> {
+        let file = std::fs::read(path).map_err(ConfigError::Io)?;
+
+        let config: WorldConfig = serde_json::from_slice(&file).map_err(ConfigError::Json)?;
+        config.validate()?;
+
+        return Ok(config);
+    }
+
+    /// Checks the values the simulation can't run with, a config coming from a file or the network must pass it
+    pub fn validate(&self) -> Result<(), ConfigError> {
+        let invalid = |reason| Err(ConfigError::Invalid(reason));
+
+        if !(self.chunk_size.is_finite() && self.chunk_size > 0.) {
+            return invalid("chunk_size must be positive");
+        }
+        if !(0. ..=MAX_CHUNK_DIST).contains(&self.chunk_load_dist) {
+            return invalid("chunk_load_dist must be between 0 and MAX_CHUNK_DIST");
+        }
+        if !(self.chunk_load_dist..=MAX_CHUNK_DIST).contains(&self.chunk_unload_dist) {
+            return invalid("chunk_unload_dist must be between chunk_load_dist and MAX_CHUNK_DIST");
+        }
+        if self.chunks_per_tick == 0 {
+            return invalid("chunks_per_tick must be at least 1");
+        }
+        if !(self.ast_density.is_finite() && self.ast_density >= 0.) {
+            return invalid("ast_density must be positive");
+        }
+        if !(self.ast_speed_max.is_finite() && self.ast_rot_speed_max.is_finite() && self.player_rot_speed.is_finite()) {
+            return invalid("the speeds must be finite");
+        }
+
+        return match self.density {
+            DensityConfig::Uniform { per_chunk } if !(per_chunk.is_finite() && per_chunk >= 0.) => {
+                invalid("per_chunk must be positive")
+            },
+            DensityConfig::Noise { max_per_chunk, .. } if !(max_per_chunk.is_finite() && max_per_chunk >= 0.) => {
+                invalid("max_per_chunk must be positive")
+            },
+            DensityConfig::Noise { scale, .. } if !(scale.is_finite() && scale > 0.) => {
+                invalid("scale must be positive")
+            },
+            // At 1 the whole noise is void, and the fill divides by zero
+            DensityConfig::Noise { void_level, .. } if !(0. ..1.).contains(&void_level) => {
+                invalid("void_level must be between 0 and 1, 1 excluded")
+            },
+            DensityConfig::Noise { octaves, .. } if !(1..=MAX_OCTAVES).contains(&octaves) => {
+                invalid("octaves must be between 1 and MAX_OCTAVES")
+            },
+            _ => Ok(()),
+        };
+    }
+
+    /// Loads the config if the file exists, the default config otherwise
+    /// An invalid file is reported and ignored, a typo shouldn't prevent the game from starting
+    pub fn load_or_default(path: impl AsRef<Path>) -> WorldConfig {
+        if !path.as_ref().exists() {
+            return WorldConfig::new();
+        }
+
+        return match WorldConfig::load(path) {
+            Ok(config) => config,
+            Err(err) => {
+                logger::warn(2, format!("Using the default world config: {}", err));
+                WorldConfig::new()
+            },
+        };
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), ConfigError> {
+        let json = serde_json::to_string_pretty(self).map_err(ConfigError::Json)?;
+
+        return std::fs::write(path, json).map_err(ConfigError::Io);
+    }
+
+    /// The chunk containing `pos`
+    pub fn chunk_of(&self, pos: cgmath::Point2<f64>) -> (i64, i64) {
+        // Floored so that a chunk covers [chunk * size, (chunk + 1) * size[ on both sides of 0
+        return ((pos.x / self.chunk_size).floor() as i64, (pos.y / self.chunk_size).floor() as i64);
+    }
+}
+
+impl Default for WorldConfig {
+    fn default() -> Self {
+        return WorldConfig::new();
+    }
+}
+
+#[derive(Debug)]
+pub enum ConfigError {
+    Io(std::io::Error),
+    Json(serde_json::Error),
+    /// The config was read but a value is out of its range
+    Invalid(&'static str),
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        return match self {
+            ConfigError::Io(err) => write!(f, "Unable to access the config file: {}", err),
+            ConfigError::Json(err) => write!(f, "Invalid config file: {}", err),
+            ConfigError::Invalid(reason) => write!(f, "Invalid config: {}", reason),
+        };
+    }
+}
+
+impl std::error::Error for ConfigError {}
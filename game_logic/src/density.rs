@@ -0,0 +1,118 @@
+use serde::{Serialize, Deserialize};
+
+use super::asteroids::splitmix64;
+
+/// Decides how many asteroids a chunk holds, the shape of the asteroid fields of a map
+/// The result must only depend on the seed and the chunk, every process regenerates the same chunks
+pub trait DensityModel: Send + Sync {
+    /// The expected amount of asteroids in the chunk, rounded by the manager
+    fn density(&self, world_seed: u64, chunk: (i64, i64)) -> f64;
+}
+
+/// The same amount of asteroids everywhere
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct UniformDensity {
+    pub per_chunk: f64,
+}
+
+impl DensityModel for UniformDensity {
+    fn density(&self, _world_seed: u64, _chunk: (i64, i64)) -> f64 {
+        return self.per_chunk;
+    }
+}
+
+/// Coherent noise over the chunks, giving belts of asteroids with clusters along them and voids between them
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct NoiseDensity {
+    /// The density at the heart of a belt
+    pub max_per_chunk: f64,
+    /// The size in chunks of the features of the noise, bigger makes wider belts and voids
+    pub scale: f64,
+    /// Between 0 and 1, the part of the noise that is empty space, bigger makes more voids
+    pub void_level: f64,
+    /// The layers of noise added together, more gives rougher edges
+    pub octaves: u32,
+}
+
+impl DensityModel for NoiseDensity {
+    fn density(&self, world_seed: u64, chunk: (i64, i64)) -> f64 {
+        let value = fractal_noise(world_seed, chunk.0 as f64 / self.scale, chunk.1 as f64 / self.scale, self.octaves);
+
+        // Ridged, the middle values of the noise become the crests of the belts
+        let ridge = 1. - (2. * value - 1.).abs();
+        let fill = ((ridge - self.void_level) / (1. - self.void_level)).clamp(0., 1.);
+
+        return self.max_per_chunk * fill * fill;
+    }
+}
+
+/// The density models a world can be configured with, custom models can still be set on the manager
+/// Externally tagged, as the binary snapshots can't read internally tagged enums
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum DensityConfig {
+    Uniform {
+        per_chunk: f64,
+    },
+    Noise {
+        max_per_chunk: f64,
+        scale: f64,
+        void_level: f64,
+        octaves: u32,
+    },
+}
+
+impl DensityConfig {
+    pub fn build(&self) -> Box<dyn DensityModel> {
+        return match *self {
+            DensityConfig::Uniform { per_chunk } => Box::new(UniformDensity { per_chunk }),
+            DensityConfig::Noise { max_per_chunk, scale, void_level, octaves } => {
+                Box::new(NoiseDensity { max_per_chunk, scale, void_level, octaves })
+            },
+        };
+    }
+}
+
+impl Default for DensityConfig {
+    fn default() -> Self {
+        return DensityConfig::Noise { max_per_chunk: 8., scale: 12., void_level: 0.5, octaves: 3 };
+    }
+}
+
+/// A random value between 0 and 1 for every point of the integer lattice
+fn lattice(seed: u64, x: i64, y: i64) -> f64 {
+    let hash = splitmix64(splitmix64(seed ^ x as u64) ^ y as u64);
+
+    return (hash >> 11) as f64 / (1u64 << 53) as f64;
+}
+
+/// Value noise, the lattice values smoothly interpolated, between 0 and 1
+fn value_noise(seed: u64, x: f64, y: f64) -> f64 {
+    let (x0, y0) = (x.floor(), y.floor());
+    let (cell_x, cell_y) = (x0 as i64, y0 as i64);
+
+    let smooth = |t: f64| t * t * (3. - 2. * t);
+    let (tx, ty) = (smooth(x - x0), smooth(y - y0));
+
+    let bottom = lattice(seed, cell_x, cell_y) * (1. - tx) + lattice(seed, cell_x + 1, cell_y) * tx;
+    let top = lattice(seed, cell_x, cell_y + 1) * (1. - tx) + lattice(seed, cell_x + 1, cell_y + 1) * tx;
+
+    return bottom * (1. - ty) + top * ty;
+}
+
+/// Octaves of value noise, each one twice as detailed and half as strong as the previous, between 0 and 1
+fn fractal_noise(seed: u64, x: f64, y: f64, octaves: u32) -> f64 {
+    let mut total = 0.;
+    let mut amplitude = 1.;
+    let mut total_amplitude = 0.;
+    let mut frequency = 1.;
+
+    for octave in 0..octaves.max(1) {
+        total += value_noise(splitmix64(seed ^ octave as u64), x * frequency, y * frequency) * amplitude;
+        total_amplitude += amplitude;
+
+        amplitude *= 0.5;
+        frequency *= 2.;
+    }
+
+    return total / total_amplitude;
+}
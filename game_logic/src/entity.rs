@@ -0,0 +1,113 @@
+use std::fmt;
+
+use fnv::FnvHashMap as HashMap;
+use serde::{Serialize, Deserialize};
+
+/// A stable handle on an entity of the world, unlike its position in a `Vec` it doesn't change
+/// when other entities are removed
+/// When an entity is removed its slot can be reused, the generation makes sure the old id never
+/// points to the new entity
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+pub struct EntityId {
+    index: u32,
+    generation: u32,
+}
+
+impl EntityId {
+    /// The id of an entity that wasn't registered in a world yet
+    pub const NONE: EntityId = EntityId { index: u32::MAX, generation: u32::MAX };
+
+    /// Rebuilds an id from its parts, like when it comes from the network
+    pub fn from_parts(index: u32, generation: u32) -> EntityId {
+        return EntityId { index, generation };
+    }
+
+    pub fn index(&self) -> u32 {
+        return self.index;
+    }
+
+    pub fn generation(&self) -> u32 {
+        return self.generation;
+    }
+
+    pub fn is_none(&self) -> bool {
+        return *self == Self::NONE;
+    }
+}
+
+impl Default for EntityId {
+    fn default() -> Self {
+        return EntityId::NONE;
+    }
+}
+
+impl fmt::Display for EntityId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        return write!(f, "{}v{}", self.index, self.generation);
+    }
+}
+
+/// Hands out the entity ids of a world, the slots of removed entities are reused with a new generation
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct EntityAllocator {
+    /// The current generation of every slot and if it is in use
+    slots: Vec<(u32, bool)>,
+    /// The slots that are free to reuse
+    free: Vec<u32>,
+}
+
+impl EntityAllocator {
+    pub fn new() -> EntityAllocator {
+        return EntityAllocator { slots: Vec::new(), free: Vec::new() };
+    }
+
+    pub fn alloc(&mut self) -> EntityId {
+        if let Some(index) = self.free.pop() {
+            let slot = &mut self.slots[index as usize];
+            slot.1 = true;
+            return EntityId { index, generation: slot.0 };
+        }
+
+        self.slots.push((0, true));
+        return EntityId { index: self.slots.len() as u32 - 1, generation: 0 };
+    }
+
+    /// Releases the id, it won't be alive anymore
+    pub fn free(&mut self, id: EntityId) {
+        if !self.is_alive(id) {
+            return;
+        }
+
+        self.slots[id.index as usize] = (id.generation.wrapping_add(1), false);
+        self.free.push(id.index);
+    }
+
+    pub fn is_alive(&self, id: EntityId) -> bool {
+        return self.slots.get(id.index as usize) == Some(&(id.generation, true));
+    }
+}
+
+/// Gives an id to the entities that don't have one and frees the ids of the entities that disappeared
+/// `lookup` maps the ids to the position of the entities and is rebuilt from `ids`
+/// An id found twice, like when an entity was copied, is only kept by the first entity
+pub(crate) fn sync_ids<'a, I>(allocator: &mut EntityAllocator, lookup: &mut HashMap<EntityId, usize>, ids: I) where I: Iterator<Item = &'a mut EntityId> {
+    let mut new_lookup = HashMap::default();
+
+    for (idx, id) in ids.enumerate() {
+        if !allocator.is_alive(*id) || new_lookup.contains_key(id) {
+            *id = allocator.alloc();
+        }
+
+        new_lookup.insert(*id, idx);
+    }
+
+    // Sorted so the slots are freed in the same order on every machine, whatever the order of the map
+    let mut removed: Vec<_> = lookup.keys().filter(|id| !new_lookup.contains_key(id)).copied().collect();
+    removed.sort_unstable();
+
+    for id in removed {
+        allocator.free(id);
+    }
+
+    *lookup = new_lookup;
+}
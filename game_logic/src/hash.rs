@@ -0,0 +1,169 @@
+use std::hash::Hasher;
+use std::time::Duration;
+
+use fnv::FnvHasher;
+
+use super::{World, Player, PlayerState, PlayerInput, PlayerScore, Asteroid, AsteroidSize, Projectile, EntityId, StoredChunk};
+
+/// Feeds the simulated state of something to a hasher, the floats are hashed bit for bit
+/// Only what changes the simulation is hashed, like the colors of a ship are left out
+pub trait StateHash {
+    fn hash_state(&self, hasher: &mut FnvHasher);
+
+    /// The hash of this value alone
+    fn state_hash(&self) -> u64 {
+        let mut hasher = FnvHasher::default();
+        self.hash_state(&mut hasher);
+
+        return hasher.finish();
+    }
+}
+
+/// Sums the hashes of the items, so the result doesn't depend on their order
+fn hash_unordered<'a, T, I>(items: I) -> u64 where T: StateHash + 'a, I: IntoIterator<Item = &'a T> {
+    return items.into_iter().fold(0u64, |acc, item| acc.wrapping_add(item.state_hash()));
+}
+
+fn write_point(hasher: &mut FnvHasher, point: cgmath::Point2<f64>) {
+    hasher.write_u64(point.x.to_bits());
+    hasher.write_u64(point.y.to_bits());
+}
+
+fn write_vector(hasher: &mut FnvHasher, vector: cgmath::Vector2<f64>) {
+    hasher.write_u64(vector.x.to_bits());
+    hasher.write_u64(vector.y.to_bits());
+}
+
+fn write_duration(hasher: &mut FnvHasher, duration: Duration) {
+    hasher.write_u128(duration.as_nanos());
+}
+
+impl StateHash for EntityId {
+    fn hash_state(&self, hasher: &mut FnvHasher) {
+        hasher.write_u32(self.index());
+        hasher.write_u32(self.generation());
+    }
+}
+
+impl StateHash for Player {
+    fn hash_state(&self, hasher: &mut FnvHasher) {
+        self.id.hash_state(hasher);
+        write_point(hasher, self.pos);
+        write_vector(hasher, self.vel);
+        hasher.write_u32(self.rot.to_bits());
+        hasher.write_u32(self.health.to_bits());
+
+        match self.state {
+            PlayerState::Alive => hasher.write_u8(0),
+            PlayerState::Dead { respawn_time } => {
+                hasher.write_u8(1);
+                write_duration(hasher, respawn_time);
+            },
+            PlayerState::Eliminated => hasher.write_u8(2),
+        }
+
+        write_duration(hasher, self.invulnerable_until);
+
+        // The cooldown decides if the next shot is fired
+        match self.last_shot {
+            Some(time) => {
+                hasher.write_u8(1);
+                write_duration(hasher, time);
+            },
+            None => hasher.write_u8(0),
+        }
+    }
+}
+
+impl StateHash for Asteroid {
+    fn hash_state(&self, hasher: &mut FnvHasher) {
+        self.id.hash_state(hasher);
+        write_point(hasher, self.pos);
+        write_vector(hasher, self.vel);
+        hasher.write_u32(self.rot.to_bits());
+        hasher.write_u32(self.rot_speed.to_bits());
+        hasher.write_u8(match self.size {
+            AsteroidSize::Small => 0,
+            AsteroidSize::Medium => 1,
+            AsteroidSize::Large => 2,
+        });
+    }
+}
+
+impl StateHash for Projectile {
+    fn hash_state(&self, hasher: &mut FnvHasher) {
+        self.id.hash_state(hasher);
+        write_point(hasher, self.pos);
+        write_vector(hasher, self.vel);
+        self.owner.hash_state(hasher);
+        write_duration(hasher, self.expire_time);
+    }
+}
+
+impl StateHash for PlayerInput {
+    fn hash_state(&self, hasher: &mut FnvHasher) {
+        hasher.write_u64(self.tick);
+        self.player.hash_state(hasher);
+        let actions = [self.thrust, self.turn_left, self.turn_right, self.fire, self.brake];
+        hasher.write_u8(actions.iter().enumerate().fold(0, |flags, (bit, on)| flags | (*on as u8) << bit));
+    }
+}
+
+impl StateHash for (EntityId, PlayerScore) {
+    fn hash_state(&self, hasher: &mut FnvHasher) {
+        self.0.hash_state(hasher);
+        hasher.write_u32(self.1.lives);
+        hasher.write_u32(self.1.small_asteroid_kills);
+        hasher.write_u32(self.1.medium_asteroid_kills);
+        hasher.write_u32(self.1.large_asteroid_kills);
+        hasher.write_u32(self.1.player_kills);
+        hasher.write_u32(self.1.deaths);
+        write_duration(hasher, self.1.survival_time);
+    }
+}
+
+impl StateHash for ((i64, i64), usize) {
+    fn hash_state(&self, hasher: &mut FnvHasher) {
+        hasher.write_i64(self.0.0);
+        hasher.write_i64(self.0.1);
+        // Fixed width, so 32 and 64 bits machines agree
+        hasher.write_u64(self.1 as u64);
+    }
+}
+
+impl StateHash for ((i64, i64), StoredChunk) {
+    fn hash_state(&self, hasher: &mut FnvHasher) {
+        hasher.write_i64(self.0.0);
+        hasher.write_i64(self.0.1);
+        hasher.write_u8(self.1.generated as u8);
+        hasher.write_u64(hash_unordered(&self.1.asteroids));
+    }
+}
+
+/// The canonical hash of the world at its current tick
+/// Every kind of entity is hashed without caring about the order of its `Vec`, so two processes
+/// simulating the same world agree even if they store the entities differently
+pub fn world_hash(world: &World) -> u64 {
+    let mut hasher = FnvHasher::default();
+
+    hasher.write_u64(world.tick());
+    hasher.write_u64(world.seed());
+    hasher.write_u64(hash_unordered(&world.players));
+    hasher.write_u64(hash_unordered(&world.asteroids));
+    hasher.write_u64(hash_unordered(&world.projectiles));
+    hasher.write_u64(hash_unordered(&world.asteroid_manager().chunks()));
+    hasher.write_u64(hash_unordered(&world.asteroid_manager().chunk_store().chunks()));
+    hasher.write_u64(hash_unordered(world.scoreboard().scores()));
+
+    // The order of the pending inputs matters, the ones of the same tick are applied in it
+    hasher.write_u64(world.pending_inputs.len() as u64);
+    for input in &world.pending_inputs {
+        input.hash_state(&mut hasher);
+    }
+    hasher.write_u64(hash_unordered(world.active_inputs.values()));
+
+    // Drives the fragments and the respawns
+    hasher.write_u128(world.rng.get_word_pos());
+
+    return hasher.finish();
+}
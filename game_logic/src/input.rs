@@ -0,0 +1,31 @@
+use serde::{Serialize, Deserialize};
+
+use super::EntityId;
+
+/// What a player wants its ship to do, the only way to control a ship
+/// Inputs are queued in the world and take effect at their tick, then stay active until
+/// the next input of the same player, so they can come from the keyboard, the network, a replay or a bot
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default, Serialize, Deserialize)]
+pub struct PlayerInput {
+    /// The tick from which the input applies
+    pub tick: u64,
+    pub player: EntityId,
+    pub thrust: bool,
+    pub turn_left: bool,
+    pub turn_right: bool,
+    pub fire: bool,
+    pub brake: bool,
+}
+
+impl PlayerInput {
+    /// An input doing nothing
+    pub fn new(tick: u64, player: EntityId) -> PlayerInput {
+        return PlayerInput { tick, player, ..Default::default() };
+    }
+
+    /// Whether both inputs ask for the same actions, whatever their tick
+    pub fn same_actions(&self, other: &PlayerInput) -> bool {
+        return self.player == other.player && self.thrust == other.thrust && self.turn_left == other.turn_left
+            && self.turn_right == other.turn_right && self.fire == other.fire && self.brake == other.brake;
+    }
+}
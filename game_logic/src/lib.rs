@@ -1,59 +1,512 @@
 pub mod asteroids;
-mod player;
+pub mod chunk_store;
+pub mod clock;
+pub mod collision;
+pub mod config;
+pub mod density;
+pub mod entity;
+pub mod hash;
+pub mod input;
+pub mod player;
+pub mod projectile;
+pub mod replay;
+pub mod score;
+pub mod snapshot;
+pub mod spatial;
 
-use std::time::Instant;
+use std::time::Duration;
 
-pub use asteroids::{Asteroid, AsteroidManager};
-pub use player::Player;
+use fnv::FnvHashMap as HashMap;
+use rand::SeedableRng;
+use rand_chacha::ChaCha8Rng;
+
+pub use asteroids::{Asteroid, AsteroidManager, AsteroidSize};
+pub use chunk_store::{ChunkStore, StoredChunk};
+pub use clock::{Clock, ManualClock, RealClock};
+pub use collision::CollisionEvent;
+pub use config::{WorldConfig, ConfigError};
+pub use density::{DensityModel, DensityConfig, UniformDensity, NoiseDensity};
+pub use entity::{EntityAllocator, EntityId};
+pub use hash::StateHash;
+pub use input::PlayerInput;
+pub use player::{Player, PlayerState};
+pub use projectile::Projectile;
+pub use replay::{Replay, ReplayRecorder, ReplayError};
+pub use score::{PlayerScore, Scoreboard};
+pub use snapshot::{WorldSnapshot, SnapshotFormat, SnapshotError};
+
+/// The duration of a single simulation tick, the world always integrates by this amount
+pub const WORLD_UPD_RATE: Duration = Duration::from_millis(1000 / 60);  // 60 times per second
 
 pub struct World {
     pub players:  Vec<Player>,
-    last_upd: Instant,
-    start_upd: Instant,
+    clock: Box<dyn Clock>,
+    /// The reading of the clock at the last update
+    last_upd: Duration,
     pub n_player_img: i32,
     pub n_asteroid_img: i32,
 
     pub asteroids: Vec<Asteroid>,
     asteroid_manager: AsteroidManager,
+
+    pub projectiles: Vec<Projectile>,
+
+    scoreboard: Scoreboard,
+
+    /// The inputs waiting for their tick
+    pending_inputs: Vec<PlayerInput>,
+    /// The input currently applied to each player
+    active_inputs: HashMap<EntityId, PlayerInput>,
+
+    entities: EntityAllocator,
+    /// Where each entity is in its `Vec`, valid between two ticks
+    player_lookup: HashMap<EntityId, usize>,
+    asteroid_lookup: HashMap<EntityId, usize>,
+    projectile_lookup: HashMap<EntityId, usize>,
+
+    config: WorldConfig,
+    /// Everything randomly generated in the world derives from this seed
+    seed: u64,
+    /// For the randomness of the simulation itself, like the fragments of asteroids
+    rng: ChaCha8Rng,
+
+    /// The number of ticks simulated since the creation of the world
+    tick: u64,
+    /// The simulated time, always `tick * WORLD_UPD_RATE`
+    time: Duration,
+    /// Time that was fed to the world but not yet simulated because it is shorter than a tick
+    accumulator: Duration,
+
+    /// The collisions that happened during the last tick
+    collisions: Vec<CollisionEvent>,
+    /// The chunks whose asteroids were loaded, unloaded or destroyed during the last tick, sorted
+    /// Left out of the snapshots, it only tells the clients of a server what to send again
+    changed_chunks: Vec<(i64, i64)>,
+    /// Streams the chunks and destroys the asteroids, a client of a server receives them instead
+    authority: bool,
+
+    /// Records the applied inputs while a replay is being recorded
+    recorder: Option<ReplayRecorder>,
 }
 
 impl World {
-    pub fn new(n_asteroid_img: i32, n_player_img: i32) -> World {
-
+    /// A world with no entity yet, the one place listing every field
+    fn blank(n_asteroid_img: i32, n_player_img: i32, seed: u64, config: WorldConfig) -> World {
         return World {
-            asteroids: vec![
-                Asteroid { pos: cgmath::Point2 { x: 2., y: 0. }, rot: 0., rot_speed: 1., vel: cgmath::Vector2 { x: 0., y: 0.}, img_idx: 1, spawn_time: 0. }
-            ],
-            players: vec![Player::new(), Player::new()],
-            last_upd: Instant::now(),
-            start_upd: Instant::now(),
+            players: Vec::new(),
+            clock: Box::new(RealClock::new()),
+            last_upd: Duration::ZERO,
             n_player_img,
             n_asteroid_img,
-            asteroid_manager: AsteroidManager::new(),
-        }
+            asteroids: Vec::new(),
+            asteroid_manager: AsteroidManager::new(config),
+            projectiles: Vec::new(),
+            scoreboard: Scoreboard::new(),
+            pending_inputs: Vec::new(),
+            active_inputs: HashMap::default(),
+            entities: EntityAllocator::new(),
+            player_lookup: HashMap::default(),
+            asteroid_lookup: HashMap::default(),
+            projectile_lookup: HashMap::default(),
+            config,
+            seed,
+            rng: ChaCha8Rng::seed_from_u64(seed),
+            tick: 0,
+            time: Duration::ZERO,
+            accumulator: Duration::ZERO,
+            collisions: Vec::new(),
+            changed_chunks: Vec::new(),
+            authority: true,
+            recorder: None,
+        };
     }
 
-    pub fn new_img_auto() -> World {
-        return World { 
-            players: vec![Player::new(), Player::new()], 
-            last_upd: Instant::now(), 
-            start_upd: Instant::now(), 
-            n_player_img: player::get_n_player_img(), 
-            n_asteroid_img: asteroids::get_n_asteroid_img(), 
-            asteroids: Vec::new(), 
-            asteroid_manager: AsteroidManager::new(),   
-        };
+    pub fn new(n_asteroid_img: i32, n_player_img: i32, seed: u64, config: WorldConfig) -> World {
+        let mut world = World::blank(n_asteroid_img, n_player_img, seed, config);
+        world.asteroids = vec![
+            Asteroid { id: EntityId::NONE, pos: cgmath::Point2 { x: 2., y: 0. }, rot: 0., rot_speed: 1., vel: cgmath::Vector2 { x: 0., y: 0.}, img_idx: 1, spawn_time: 0., size: AsteroidSize::Medium }
+        ];
+        world.players = vec![Player::new(), Player::new()];
+
+        world.sync_entities();
+
+        return world;
+    }
+
+    pub fn new_img_auto(config: WorldConfig) -> World {
+        let mut world = World::blank(asteroids::get_n_asteroid_img(), player::get_n_player_img(), rand::random(), config);
+        world.players = vec![Player::new(), Player::new()];
+
+        world.sync_entities();
+
+        return world;
     }
     
+    /// Captures the whole state of the world, restoring it with `from_snapshot` gives back the same simulation
+    pub fn snapshot(&self) -> WorldSnapshot {
+        let mut active_inputs: Vec<_> = self.active_inputs.values().copied().collect();
+        active_inputs.sort_unstable_by_key(|input| input.player);
+
+        return WorldSnapshot {
+            version: snapshot::SNAPSHOT_VERSION,
+            config: self.config,
+            seed: self.seed,
+            rng: self.rng.clone(),
+            tick: self.tick,
+            time: self.time,
+            accumulator: self.accumulator,
+            n_player_img: self.n_player_img,
+            n_asteroid_img: self.n_asteroid_img,
+            players: self.players.clone(),
+            asteroids: self.asteroids.clone(),
+            projectiles: self.projectiles.clone(),
+            entities: self.entities.clone(),
+            chunks: self.asteroid_manager.chunks(),
+            stored_chunks: self.asteroid_manager.chunk_store().chunks(),
+            scoreboard: self.scoreboard.clone(),
+            pending_inputs: self.pending_inputs.clone(),
+            active_inputs,
+            collisions: self.collisions.clone(),
+        };
+    }
+
+    /// Restores a world from a snapshot, it runs on a new `RealClock`
+    pub fn from_snapshot(snapshot: WorldSnapshot) -> World {
+        let mut asteroid_manager = AsteroidManager::from_chunks(&snapshot.chunks, snapshot.config);
+        asteroid_manager.rebuild_index(&snapshot.asteroids);
+        for (chunk, state) in snapshot.stored_chunks {
+            asteroid_manager.chunk_store_mut().store(chunk, state);
+        }
+
+        let mut world = World::blank(snapshot.n_asteroid_img, snapshot.n_player_img, snapshot.seed, snapshot.config);
+        world.players = snapshot.players;
+        world.asteroids = snapshot.asteroids;
+        world.asteroid_manager = asteroid_manager;
+        world.projectiles = snapshot.projectiles;
+        world.scoreboard = snapshot.scoreboard;
+        world.pending_inputs = snapshot.pending_inputs;
+        world.active_inputs = snapshot.active_inputs.into_iter().map(|input| (input.player, input)).collect();
+        world.entities = snapshot.entities;
+        world.rng = snapshot.rng;
+        world.tick = snapshot.tick;
+        world.time = snapshot.time;
+        world.accumulator = snapshot.accumulator;
+        world.collisions = snapshot.collisions;
+
+        world.sync_entities();
+
+        return world;
+    }
+
+    /// Replaces the clock driving `update`, the time already simulated is kept
+    pub fn set_clock(&mut self, clock: Box<dyn Clock>) {
+        self.last_upd = clock.now();
+        self.clock = clock;
+    }
+
+    /// Advances the world by the time elapsed on its clock since the last call
     pub fn update(&mut self) {
-        let delta_t = self.last_upd.elapsed().as_secs_f64();
-        self.last_upd = Instant::now();
-        let time = self.start_upd.elapsed().as_secs_f32();
-        
+        let now = self.clock.now();
+        let elapsed = now.saturating_sub(self.last_upd);
+        self.last_upd = now;
+
+        self.advance(elapsed);
+    }
+
+    /// Feeds `elapsed` to the world and simulates as many fixed ticks as it covers
+    /// The remainder is kept for the next call, returns the number of ticks simulated
+    /// The clock can cap the number of ticks, the time it drops is lost
+    pub fn advance(&mut self, elapsed: Duration) -> u32 {
+        self.accumulator += elapsed;
+        let max_ticks = self.clock.max_ticks_per_update();
+
+        let mut n_ticks = 0;
+        while self.accumulator >= WORLD_UPD_RATE {
+            if max_ticks.is_some_and(|max| n_ticks >= max) {
+                logger::warn(2, format!("World is running behind, skipping {:?} of simulation", self.accumulator));
+                self.accumulator = Duration::ZERO;
+                break;
+            }
+
+            self.accumulator -= WORLD_UPD_RATE;
+            self.step();
+            n_ticks += 1;
+        }
+
+        return n_ticks;
+    }
+
+    /// Simulates exactly one tick of `WORLD_UPD_RATE`
+    pub fn step(&mut self) {
+        let delta_t = WORLD_UPD_RATE.as_secs_f64();
+
+        self.tick += 1;
+        self.time += WORLD_UPD_RATE;
+        let now = self.time;
+
+        self.collisions.clear();
+        self.changed_chunks.clear();
+        self.apply_inputs();
+
         asteroids::update_asteroids(self, delta_t);
-        player::update_players(self, delta_t);
+        player::update_players(self, delta_t, now);
+        projectile::update_projectiles(self, delta_t, now);
+
+        let mut collisions = collision::detect_collisions(self);
+        self.collisions.append(&mut collisions);
+        player::handle_player_collisions(self, now);
 
-        self.asteroid_manager.clean_asteroids(&mut self.asteroids, &self.players);
-        self.asteroid_manager.add_asteroids(&mut self.asteroids, &self.players, time, self.n_asteroid_img);
+        if self.authority {
+            self.asteroid_manager.stream(&mut self.asteroids, &self.players, now, self.n_asteroid_img, self.seed);
+            self.changed_chunks.append(&mut self.asteroid_manager.take_changed_chunks());
+        }
+        self.asteroid_manager.update_index(&self.asteroids);
+
+        self.changed_chunks.sort_unstable();
+        self.changed_chunks.dedup();
+
+        self.sync_entities();
     }
-}
\ No newline at end of file
+
+    /// Gives ids to the new entities, frees the ones of the removed entities and rebuilds the lookups
+    /// Entities pushed directly in the `Vec`s get their id here, at the latest at the end of the next tick
+    fn sync_entities(&mut self) {
+        entity::sync_ids(&mut self.entities, &mut self.player_lookup, self.players.iter_mut().map(|player| &mut player.id));
+        entity::sync_ids(&mut self.entities, &mut self.asteroid_lookup, self.asteroids.iter_mut().map(|ast| &mut ast.id));
+        entity::sync_ids(&mut self.entities, &mut self.projectile_lookup, self.projectiles.iter_mut().map(|projectile| &mut projectile.id));
+
+        for player in &self.players {
+            self.scoreboard.get_mut(player.id);
+        }
+    }
+
+    /// Adds a player to the world, returns its id
+    pub fn add_player(&mut self, mut player: Player) -> EntityId {
+        player.id = EntityId::NONE;
+        self.players.push(player);
+        self.sync_entities();
+
+        return self.players[self.players.len() - 1].id;
+    }
+
+    /// Removes a player from the world, its score is kept
+    pub fn remove_player(&mut self, id: EntityId) -> Option<Player> {
+        let idx = *self.player_lookup.get(&id)?;
+        let player = self.players.remove(idx);
+
+        self.active_inputs.remove(&id);
+        self.pending_inputs.retain(|input| input.player != id);
+        self.sync_entities();
+
+        return Some(player);
+    }
+
+    pub fn player(&self, id: EntityId) -> Option<&Player> {
+        return self.player_lookup.get(&id).map(|idx| &self.players[*idx]);
+    }
+
+    pub fn player_mut(&mut self, id: EntityId) -> Option<&mut Player> {
+        return self.player_lookup.get(&id).map(|idx| &mut self.players[*idx]);
+    }
+
+    pub fn asteroid(&self, id: EntityId) -> Option<&Asteroid> {
+        return self.asteroid_lookup.get(&id).map(|idx| &self.asteroids[*idx]);
+    }
+
+    /// The spatial index follows the asteroid at the end of the next tick, like when it moves
+    pub fn asteroid_mut(&mut self, id: EntityId) -> Option<&mut Asteroid> {
+        return self.asteroid_lookup.get(&id).map(|idx| &mut self.asteroids[*idx]);
+    }
+
+    /// Adds an asteroid to the world, returns its id
+    pub fn add_asteroid(&mut self, mut asteroid: Asteroid) -> EntityId {
+        asteroid.id = EntityId::NONE;
+        self.asteroids.push(asteroid);
+        self.asteroid_manager.rebuild_index(&self.asteroids);
+        self.sync_entities();
+
+        return self.asteroids[self.asteroids.len() - 1].id;
+    }
+
+    /// Removes asteroids without fragments, the unknown ids are ignored
+    pub fn remove_asteroids(&mut self, ids: &[EntityId]) {
+        let mut idxs: Vec<_> = ids.iter().filter_map(|id| self.asteroid_lookup.get(id).copied()).collect();
+        idxs.sort_unstable();
+        idxs.dedup();
+
+        asteroids::remove_asteroids(&mut self.asteroids, &idxs);
+
+        self.asteroid_manager.rebuild_index(&self.asteroids);
+        self.sync_entities();
+    }
+
+    pub fn projectile(&self, id: EntityId) -> Option<&Projectile> {
+        return self.projectile_lookup.get(&id).map(|idx| &self.projectiles[*idx]);
+    }
+
+    /// Adds a projectile to the world, returns its id
+    pub fn add_projectile(&mut self, mut projectile: Projectile) -> EntityId {
+        projectile.id = EntityId::NONE;
+        self.projectiles.push(projectile);
+        self.sync_entities();
+
+        return self.projectiles[self.projectiles.len() - 1].id;
+    }
+
+    /// The ids of the players, in the order of `players`
+    pub fn player_ids(&self) -> Vec<EntityId> {
+        return self.players.iter().map(|player| player.id).collect();
+    }
+
+    /// Queues an input, it will be applied at the start of its tick
+    /// Inputs for a tick already simulated are applied at the next tick
+    pub fn queue_input(&mut self, input: PlayerInput) {
+        self.pending_inputs.push(input);
+    }
+
+    /// The input currently applied to a player
+    pub fn input(&self, player: EntityId) -> Option<&PlayerInput> {
+        return self.active_inputs.get(&player);
+    }
+
+    /// Makes the queued inputs whose tick came the active ones, in the order of their ticks
+    fn apply_inputs(&mut self) {
+        let tick = self.tick;
+
+        let mut due: Vec<_> = self.pending_inputs.iter().filter(|input| input.tick <= tick).copied().collect();
+        self.pending_inputs.retain(|input| input.tick > tick);
+
+        due.sort_by_key(|input| input.tick);  // Stable, so inputs of the same tick keep their queue order
+
+        if let Some(recorder) = &mut self.recorder {
+            recorder.record(tick, &due);
+        }
+
+        for input in due {
+            self.active_inputs.insert(input.player, input);
+        }
+    }
+
+    /// Starts recording a replay from the current state, a recording already running is dropped
+    pub fn start_recording(&mut self) {
+        self.recorder = Some(ReplayRecorder::new(self));
+    }
+
+    /// Stops the recording and returns the replay of everything simulated since it started
+    pub fn stop_recording(&mut self) -> Option<Replay> {
+        let recorder = self.recorder.take()?;
+        return Some(recorder.finish(self));
+    }
+
+    pub fn is_recording(&self) -> bool {
+        return self.recorder.is_some();
+    }
+
+    /// The canonical hash of the simulated state, see `hash::world_hash`
+    /// Two worlds at the same tick with the same hash are in sync
+    pub fn state_hash(&self) -> u64 {
+        return hash::world_hash(self);
+    }
+
+    /// Removes an asteroid and replaces it with its fragments
+    pub fn destroy_asteroid(&mut self, id: EntityId) {
+        if let Some(idx) = self.asteroid_lookup.get(&id) {
+            self.destroy_asteroids(&[*idx]);
+        }
+    }
+
+    /// Removes several asteroids at once and replaces them with their fragments
+    /// The indices are the ones before any removal, the fragments are added at the end
+    pub fn destroy_asteroids(&mut self, idxs: &[usize]) {
+        let mut idxs = idxs.to_vec();
+        idxs.sort_unstable();
+        idxs.dedup();
+
+        let mut fragments = Vec::new();
+
+        for asteroid in asteroids::remove_asteroids(&mut self.asteroids, &idxs) {
+            self.changed_chunks.push(self.config.chunk_of(asteroid.pos));
+            fragments.append(&mut asteroids::split_asteroid(&asteroid, &mut self.rng, self.time.as_secs_f32(), &self.config));
+        }
+
+        self.changed_chunks.extend(fragments.iter().map(|fragment| self.config.chunk_of(fragment.pos)));
+        self.asteroids.append(&mut fragments);
+
+        self.asteroid_manager.rebuild_index(&self.asteroids);
+        self.sync_entities();
+    }
+
+    /// The collisions detected during the last simulated tick
+    pub fn collisions(&self) -> &[CollisionEvent] {
+        return &self.collisions;
+    }
+
+    /// The chunks whose asteroids changed otherwise than by moving during the last tick, sorted
+    pub fn changed_chunks(&self) -> &[(i64, i64)] {
+        return &self.changed_chunks;
+    }
+
+    /// The chunks that are loaded or hold asteroids, sorted
+    pub fn active_chunks(&self) -> Vec<(i64, i64)> {
+        let mut chunks: Vec<_> = self.asteroid_manager.chunks().into_iter().map(|(chunk, _)| chunk)
+            .chain(self.asteroid_manager.index().cells().map(|(cell, _)| *cell))
+            .collect();
+        chunks.sort_unstable();
+        chunks.dedup();
+
+        return chunks;
+    }
+
+    /// The asteroids in a chunk at the end of the last tick
+    pub fn chunk_asteroids(&self, chunk: (i64, i64)) -> Vec<&Asteroid> {
+        return self.asteroid_manager.index().cell(chunk).iter().map(|idx| &self.asteroids[*idx]).collect();
+    }
+
+    /// Without authority the world leaves the chunks and the destruction of the asteroids to someone else, like a server
+    /// Its projectiles still stop on the asteroids they hit
+    pub fn set_authority(&mut self, authority: bool) {
+        self.authority = authority;
+    }
+
+    pub fn has_authority(&self) -> bool {
+        return self.authority;
+    }
+
+    /// The scores of the session, by player id
+    pub fn scoreboard(&self) -> &Scoreboard {
+        return &self.scoreboard;
+    }
+
+    pub fn asteroid_manager(&self) -> &AsteroidManager {
+        return &self.asteroid_manager;
+    }
+
+    /// Writes the chunks the store can't keep in memory to `dir`, instead of forgetting them
+    /// Only for a world with authority over the asteroids, the others would read chunks the authority doesn't have
+    pub fn set_chunk_store_dir(&mut self, dir: Option<std::path::PathBuf>) {
+        self.asteroid_manager.chunk_store_mut().set_dir(dir);
+    }
+
+    pub fn config(&self) -> &WorldConfig {
+        return &self.config;
+    }
+
+    pub fn seed(&self) -> u64 {
+        return self.seed;
+    }
+
+    pub fn tick(&self) -> u64 {
+        return self.tick;
+    }
+
+    /// The simulated time since the creation of the world
+    pub fn time(&self) -> Duration {
+        return self.time;
+    }
+
+    /// How far the world is between the last tick and the next one, between 0 and 1
+    /// Used by the renderer to interpolate when rendering faster than the tick rate
+    pub fn tick_progress(&self) -> f64 {
+        return self.accumulator.as_secs_f64() / WORLD_UPD_RATE.as_secs_f64();
+    }
+}
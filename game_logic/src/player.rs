@@ -1,14 +1,31 @@
-use super::World;
+use std::time::Duration;
+
+use cgmath::{Point2, Vector2, InnerSpace};
+use rand::Rng;
+
+use super::{World, Projectile, CollisionEvent, PlayerInput, EntityId};
+use super::score::Scoreboard;
+use super::spatial::SpatialIndex;
 
 use serde::{Serialize, Deserialize};
 
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum PlayerState {
+    Alive,
+    /// The ship was destroyed and comes back at `respawn_time`, in simulated time
+    Dead {
+        respawn_time: Duration,
+    },
+    /// Out of lives, the player won't come back this session
+    Eliminated,
+}
+
 #[derive(Debug, Clone, Copy, Serialize, Deserialize)]
 pub struct Player {
+    /// Given by the world when the player is added to it
+    pub id: EntityId,
     pub pos: cgmath::Point2<f64>,
     pub vel: cgmath::Vector2<f64>,
-    pub press_forward: bool,
-    pub press_right: bool,
-    pub press_left: bool,
     pub rot: f32,
     pub accent_color_0: [f32; 4],
     pub accent_color_1: [f32; 4],
@@ -17,22 +34,40 @@ pub struct Player {
     pub accent_flame_color: [f32; 4],
     pub flame_frame: f32,
     pub player_img: i32,
-    #[serde(with = "serde_millis")]
-    last_frame_upd: std::time::Instant,
+    pub health: f32,
+    pub state: PlayerState,
+    /// The simulated time until which the player can't take damage
+    pub invulnerable_until: Duration,
+    /// The simulated time at which the flame animation last changed frame
+    last_frame_upd: Duration,
+    /// The simulated time of the last shot, to enforce the cooldown
+    pub(crate) last_shot: Option<Duration>,
 }
 
 impl Player {
-    const ROT_SPEED: f32 = 4.5;
+    /// The radius of the hitbox of the ship
+    const RADIUS: f64 = 0.015;
+    /// The fraction of its speed the ship loses per second when braking
+    const BRAKE_STRENGTH: f64 = 2.;
+    /// The min time between two shots
+    const FIRE_COOLDOWN: Duration = Duration::from_millis(250);
+
+    pub const MAX_HEALTH: f32 = 100.;
+    /// The damage taken when hitting a medium asteroid at a speed of 1
+    const DAMAGE_PER_SPEED: f32 = 80.;
+    /// How much of its speed the ship keeps when bouncing off an asteroid
+    const BOUNCE_RESTITUTION: f64 = 0.5;
+    const RESPAWN_DELAY: Duration = Duration::from_secs(3);
+    /// How long a player can't take damage after respawning
+    const INVULNERABILITY: Duration = Duration::from_secs(2);
     
     /// Creates a player at 0, 0 with no vel and rot = 0
     pub fn new() -> Player {
         return Player { 
+            id: EntityId::NONE,
             pos: cgmath::Point2 { x: 0., y: 0. }, 
             vel: cgmath::Vector2 { x: 0., y: 0. }, 
             rot: 0.,
-            press_forward: false,
-            press_left: false,
-            press_right: false,
             accent_color_0: [1., 0.06, 0.06, 1.],
             accent_color_1: [0.3, 0.85, 1., 1.],
             accent_color_2: [1., 1., 1., 1.],
@@ -40,29 +75,163 @@ impl Player {
             accent_flame_color: [1., 0.5, 0.5, 1.],
             flame_frame: 0.,
             player_img: 0, 
-            last_frame_upd: std::time::Instant::now(),
+            health: Self::MAX_HEALTH,
+            state: PlayerState::Alive,
+            invulnerable_until: Duration::ZERO,
+            last_frame_upd: Duration::ZERO,
+            last_shot: None,
         };
     }
+
+    pub fn radius(&self) -> f64 {
+        return Self::RADIUS;
+    }
+
+    pub fn is_alive(&self) -> bool {
+        return self.state == PlayerState::Alive;
+    }
+
+    pub fn is_invulnerable(&self, now: Duration) -> bool {
+        return now < self.invulnerable_until;
+    }
+
+    /// Removes health unless the player is invulnerable, kills the player when it reaches 0
+    /// Returns if this damage killed the player
+    pub fn damage(&mut self, amount: f32, now: Duration) -> bool {
+        if !self.is_alive() || self.is_invulnerable(now) {
+            return false;
+        }
+
+        self.health -= amount;
+
+        if self.health <= 0. {
+            self.health = 0.;
+            self.state = PlayerState::Dead { respawn_time: now + Self::RESPAWN_DELAY };
+            self.vel = Vector2 { x: 0., y: 0. };
+            self.flame_frame = -1.5;
+            return true;
+        }
+
+        return false;
+    }
+
+    /// Brings a dead player back at `pos`, with full health and a moment of invulnerability
+    pub fn respawn(&mut self, pos: Point2<f64>, now: Duration) {
+        self.pos = pos;
+        self.vel = Vector2 { x: 0., y: 0. };
+        self.health = Self::MAX_HEALTH;
+        self.state = PlayerState::Alive;
+        self.invulnerable_until = now + Self::INVULNERABILITY;
+    }
 }
 
-pub fn update_players(world: &mut World, delta_t: f64) {
+/// How close to an asteroid a player can respawn
+const RESPAWN_SAFE_DIST: f64 = 0.3;
+/// How far from where it died a player can respawn
+const RESPAWN_SEARCH_RADIUS: f64 = 2.;
+const RESPAWN_N_TRIES: usize = 32;
+
+/// Picks a place around `around` with no asteroid close by
+/// If every try has an asteroid nearby, the one with the fewest is taken
+pub fn pick_respawn_pos<R>(around: Point2<f64>, index: &SpatialIndex, rng: &mut R) -> Point2<f64> where R: Rng {
+    let mut best = around;
+    let mut best_n_nearby = usize::MAX;
+
+    for try_idx in 0..RESPAWN_N_TRIES {
+        let candidate = if try_idx == 0 {
+            around
+        } else {
+            let angle = rng.gen_range(-std::f64::consts::PI..std::f64::consts::PI);
+            let dist = rng.gen_range(0.0..RESPAWN_SEARCH_RADIUS);
+            around + Vector2 { x: angle.cos(), y: angle.sin() } * dist
+        };
+
+        let n_nearby = index.query_radius(candidate, RESPAWN_SAFE_DIST).len();
+
+        if n_nearby == 0 {
+            return candidate;
+        }
+        if n_nearby < best_n_nearby {
+            best = candidate;
+            best_n_nearby = n_nearby;
+        }
+    }
+
+    return best;
+}
+
+/// Counts the death of a player on the scoreboard, the player is eliminated when out of lives
+/// `killer` is the player that fired the fatal shot, if any
+pub fn record_death(player: &mut Player, killer: Option<EntityId>, scoreboard: &mut Scoreboard) {
+    if let Some(killer) = killer {
+        if killer != player.id {
+            scoreboard.record_player_kill(killer);
+        }
+    }
+
+    if scoreboard.record_death(player.id) == 0 {
+        player.state = PlayerState::Eliminated;
+    }
+}
+
+/// Applies the player-asteroid collisions of the tick, the ships bounce off and take damage
+/// depending on how fast they hit
+pub fn handle_player_collisions(world: &mut World, now: Duration) {
+    for event in &world.collisions {
+        if let CollisionEvent::PlayerAsteroid { player, asteroid, contact } = *event {
+            let (Some(player_idx), Some(ast_idx)) = (world.player_lookup.get(&player), world.asteroid_lookup.get(&asteroid)) else {
+                continue;
+            };
+            let player = &mut world.players[*player_idx];
+            let asteroid = &world.asteroids[*ast_idx];
+
+            player.pos -= contact.normal * contact.depth;
+
+            let impact_speed = (player.vel - asteroid.vel).dot(contact.normal);
+            if impact_speed <= 0. {  // Already moving away from the asteroid
+                continue;
+            }
+
+            player.vel -= contact.normal * impact_speed * (1. + Player::BOUNCE_RESTITUTION);
+            if player.damage(impact_speed as f32 * Player::DAMAGE_PER_SPEED * asteroid.size.scale() as f32, now) {
+                record_death(player, None, &mut world.scoreboard);
+            }
+        }
+    }
+}
+
+/// Moves the players according to their active input
+/// `now` is the simulated time of the tick being computed
+pub fn update_players(world: &mut World, delta_t: f64, now: Duration) {
     for player in &mut world.players {
-       
-        if player.press_right {
-            player.rot -= Player::ROT_SPEED * delta_t as f32;
+        match player.state {
+            PlayerState::Alive => {},
+            PlayerState::Dead { respawn_time } if now >= respawn_time => {
+                let pos = pick_respawn_pos(player.pos, world.asteroid_manager.index(), &mut world.rng);
+                player.respawn(pos, now);
+            },
+            PlayerState::Dead { .. } | PlayerState::Eliminated => continue,
         }
-        if player.press_left {
-            player.rot += Player::ROT_SPEED * delta_t as f32;
+
+        world.scoreboard.add_survival_time(player.id, Duration::from_secs_f64(delta_t));
+
+        let input = world.active_inputs.get(&player.id).copied().unwrap_or_else(|| PlayerInput::new(0, player.id));
+
+        if input.turn_right {
+            player.rot -= world.config.player_rot_speed * delta_t as f32;
+        }
+        if input.turn_left {
+            player.rot += world.config.player_rot_speed * delta_t as f32;
         }
 
-        if player.press_forward {
+        if input.thrust {
             let x = player.rot.cos() as f64;
             let y = player.rot.sin() as f64;
             
             player.vel += cgmath::Vector2 { x, y } * delta_t;
 
-            if player.last_frame_upd.elapsed().as_secs_f32() > 0.2 {
-                player.last_frame_upd = std::time::Instant::now();
+            if now.saturating_sub(player.last_frame_upd).as_secs_f32() > 0.2 {
+                player.last_frame_upd = now;
     
                 player.flame_frame += 0.25;
                 player.flame_frame %= 1.;
@@ -71,7 +240,16 @@ pub fn update_players(world: &mut World, delta_t: f64) {
             player.flame_frame = -1.5;
         }
         
+        if input.brake {
+            player.vel *= (1. - Player::BRAKE_STRENGTH * delta_t).max(0.);
+        }
+        
         player.pos += player.vel * delta_t;
+
+        if input.fire && player.last_shot.is_none_or(|t| now.saturating_sub(t) >= Player::FIRE_COOLDOWN) {
+            player.last_shot = Some(now);
+            world.projectiles.push(Projectile::fire(player, now));
+        }
     }
 }
 
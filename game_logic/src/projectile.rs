@@ -0,0 +1,102 @@
+use std::time::Duration;
+
+use cgmath::{Point2, Vector2};
+use serde::{Serialize, Deserialize};
+
+use super::{World, Player, Asteroid, CollisionEvent, EntityId};
+use super::collision::circle_overlap;
+use super::player::record_death;
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct Projectile {
+    /// Given by the world when the projectile is added to it
+    pub id: EntityId,
+    pub pos: Point2<f64>,
+    pub vel: Vector2<f64>,
+    /// The player that fired it
+    pub owner: EntityId,
+    /// The simulated time at which the projectile disappears
+    pub expire_time: Duration,
+}
+
+impl Projectile {
+    /// The speed of a projectile relative to the ship that fired it
+    const SPEED: f64 = 1.5;
+    const LIFETIME: Duration = Duration::from_millis(1200);
+    /// The radius of the hitbox of a projectile
+    pub const RADIUS: f64 = 0.004;
+    /// The health removed from a player hit by a projectile
+    const DAMAGE: f32 = 35.;
+
+    /// Fires a projectile from the nose of the ship, it keeps the velocity of the ship
+    pub fn fire(player: &Player, now: Duration) -> Projectile {
+        let dir = Vector2 { x: player.rot.cos() as f64, y: player.rot.sin() as f64 };
+
+        return Projectile {
+            id: EntityId::NONE,
+            pos: player.pos + dir * player.radius(),
+            vel: player.vel + dir * Self::SPEED,
+            owner: player.id,
+            expire_time: now + Self::LIFETIME,
+        };
+    }
+
+    pub fn radius(&self) -> f64 {
+        return Self::RADIUS;
+    }
+}
+
+/// Moves the projectiles, removes the expired ones, destroys the asteroids and damages the players they hit
+pub fn update_projectiles(world: &mut World, delta_t: f64, now: Duration) {
+    for projectile in &mut world.projectiles {
+        projectile.pos += projectile.vel * delta_t;
+    }
+
+    world.projectiles.retain(|projectile| projectile.expire_time > now);
+
+    let mut hit_asteroids = Vec::new();
+
+    world.projectiles.retain(|projectile| {
+        let nearby = world.asteroid_manager.index().query_radius(projectile.pos, projectile.radius() + Asteroid::MAX_RADIUS);
+
+        for ast_idx in nearby {
+            if hit_asteroids.contains(&ast_idx) {  // Already destroyed by another projectile this tick
+                continue;
+            }
+
+            let ast = &world.asteroids[ast_idx];
+            if circle_overlap(projectile.pos, projectile.radius(), ast.pos, ast.radius()).is_some() {
+                // Without authority the asteroid is only destroyed when the server says so
+                if world.authority {
+                    hit_asteroids.push(ast_idx);
+                    world.scoreboard.record_asteroid_kill(projectile.owner, ast.size);
+                    world.collisions.push(CollisionEvent::ProjectileAsteroid { owner: projectile.owner, size: ast.size, pos: ast.pos });
+                }
+                return false;
+            }
+        }
+
+        for player in &mut world.players {
+            // A ship can't shoot itself, the projectiles spawn inside of it
+            if player.id == projectile.owner || !player.is_alive() {
+                continue;
+            }
+
+            if circle_overlap(projectile.pos, projectile.radius(), player.pos, player.radius()).is_some() {
+                let killed = player.damage(Projectile::DAMAGE, now);
+                if killed {
+                    record_death(player, Some(projectile.owner), &mut world.scoreboard);
+                }
+
+                world.collisions.push(CollisionEvent::ProjectilePlayer { owner: projectile.owner, player: player.id, killed });
+                return false;
+            }
+        }
+
+        return true;
+    });
+
+    if !hit_asteroids.is_empty() {
+        world.destroy_asteroids(&hit_asteroids);
+    }
+}
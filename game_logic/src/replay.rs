@@ -0,0 +1,193 @@
+use std::fmt;
+use std::path::Path;
+
+use serde::{Serialize, Deserialize};
+
+use super::{World, WorldSnapshot, SnapshotError, PlayerInput};
+
+/// Bumped every time the content of a replay changes, old replays are refused
+pub const REPLAY_VERSION: u32 = 1;
+
+/// The first bytes of a replay file
+const REPLAY_MAGIC: &[u8; 4] = b"ASTR";
+
+#[derive(Debug)]
+pub enum ReplayError {
+    /// Reading or writing the file failed, the errors are the same as for the snapshots
+    File(SnapshotError),
+    /// The replay was made by another version of the game
+    Version {
+        found: u32,
+    },
+    /// Playing the replay didn't end in the recorded state, the simulation isn't deterministic
+    Desync {
+        expected: u64,
+        found: u64,
+    },
+}
+
+impl fmt::Display for ReplayError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        return match self {
+            ReplayError::File(err) => write!(f, "{}", err),
+            ReplayError::Version { found } => write!(f, "Replay version {} is not supported, expected {}", found, REPLAY_VERSION),
+            ReplayError::Desync { expected, found } => write!(f, "Replay desynced, expected state {:016x} but got {:016x}", expected, found),
+        };
+    }
+}
+
+impl std::error::Error for ReplayError {}
+
+impl From<SnapshotError> for ReplayError {
+    fn from(err: SnapshotError) -> Self {
+        return ReplayError::File(err);
+    }
+}
+
+impl From<std::io::Error> for ReplayError {
+    fn from(err: std::io::Error) -> Self {
+        return ReplayError::File(SnapshotError::Io(err));
+    }
+}
+
+impl From<bincode::Error> for ReplayError {
+    fn from(err: bincode::Error) -> Self {
+        return ReplayError::File(SnapshotError::Binary(err));
+    }
+}
+
+/// A recorded session: the world at the start and every input applied after it
+/// Playing the inputs over the start world gives back the same session, tick for tick
+/// Only the inputs are recorded, players added or removed during the recording break the replay
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Replay {
+    pub version: u32,
+    pub start: WorldSnapshot,
+    /// Every applied input, stamped with the tick it was applied at and in the order they were applied
+    pub inputs: Vec<PlayerInput>,
+    /// The number of ticks recorded
+    pub n_ticks: u64,
+    /// The state hash of the world at the end of the recording
+    pub end_hash: u64,
+}
+
+impl Replay {
+    /// The world at the start of the replay, with every input queued
+    /// Stepping it `n_ticks` times plays the replay, so it can also be watched in real time
+    pub fn start_world(&self) -> World {
+        let mut world = World::from_snapshot(self.start.clone());
+
+        for input in &self.inputs {
+            world.queue_input(*input);
+        }
+
+        return world;
+    }
+
+    /// Plays the whole replay headlessly and returns the world at the end
+    pub fn run(&self) -> World {
+        return self.run_with_progress(|_| ());
+    }
+
+    /// Like `run`, `on_tick` is given the number of ticks played after each of them
+    pub fn run_with_progress<F>(&self, mut on_tick: F) -> World where F: FnMut(u64) {
+        let mut world = World::from_snapshot(self.start.clone());
+        let mut next_input = 0;
+
+        for _ in 0..self.n_ticks {
+            // Only the inputs of the coming tick are queued so the queue stays short on long replays
+            let tick = world.tick() + 1;
+            while next_input < self.inputs.len() && self.inputs[next_input].tick <= tick {
+                world.queue_input(self.inputs[next_input]);
+                next_input += 1;
+            }
+
+            world.step();
+            on_tick(world.tick() - self.start.tick);
+        }
+
+        return world;
+    }
+
+    /// Plays the replay and checks it ends in the recorded state
+    pub fn verify(&self) -> Result<World, ReplayError> {
+        return self.verify_with_progress(|_| ());
+    }
+
+    /// Like `verify`, `on_tick` is given the number of ticks played after each of them
+    pub fn verify_with_progress<F>(&self, on_tick: F) -> Result<World, ReplayError> where F: FnMut(u64) {
+        let world = self.run_with_progress(on_tick);
+        let found = world.state_hash();
+
+        if found != self.end_hash {
+            return Err(ReplayError::Desync { expected: self.end_hash, found });
+        }
+
+        return Ok(world);
+    }
+
+    pub fn to_bytes(&self) -> Result<Vec<u8>, ReplayError> {
+        let mut output = REPLAY_MAGIC.to_vec();
+        bincode::serialize_into(&mut output, self)?;
+
+        return Ok(output);
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Replay, ReplayError> {
+        let bytes = bytes.strip_prefix(REPLAY_MAGIC.as_slice()).unwrap_or(bytes);
+
+        // The version is the first field, so it can be read alone
+        let found: u32 = bincode::deserialize(bytes)?;
+        if found != REPLAY_VERSION {
+            return Err(ReplayError::Version { found });
+        }
+
+        return Ok(bincode::deserialize(bytes)?);
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), ReplayError> {
+        std::fs::write(path, self.to_bytes()?)?;
+
+        return Ok(());
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> Result<Replay, ReplayError> {
+        return Replay::from_bytes(&std::fs::read(path)?);
+    }
+}
+
+/// Records the inputs applied by a world, made by `World::start_recording`
+#[derive(Debug, Clone)]
+pub struct ReplayRecorder {
+    start: WorldSnapshot,
+    inputs: Vec<PlayerInput>,
+}
+
+impl ReplayRecorder {
+    pub(crate) fn new(world: &World) -> ReplayRecorder {
+        let mut start = world.snapshot();
+        // The pending inputs are recorded when they are applied, keeping them would apply them twice
+        start.pending_inputs.clear();
+        // The replay is played tick by tick, the time between ticks doesn't matter
+        start.accumulator = std::time::Duration::ZERO;
+
+        return ReplayRecorder { start, inputs: Vec::new() };
+    }
+
+    /// Called by the world with the inputs it applies at `tick`
+    pub(crate) fn record(&mut self, tick: u64, inputs: &[PlayerInput]) {
+        for input in inputs {
+            self.inputs.push(PlayerInput { tick, ..*input });
+        }
+    }
+
+    pub(crate) fn finish(self, world: &World) -> Replay {
+        return Replay {
+            version: REPLAY_VERSION,
+            n_ticks: world.tick() - self.start.tick,
+            start: self.start,
+            inputs: self.inputs,
+            end_hash: world.state_hash(),
+        };
+    }
+}
@@ -0,0 +1,128 @@
+use std::time::Duration;
+
+use serde::{Serialize, Deserialize};
+
+use super::{AsteroidSize, EntityId};
+
+/// The lives of a player at the start of a session
+pub const START_LIVES: u32 = 3;
+
+const SMALL_ASTEROID_POINTS: u64 = 100;
+const MEDIUM_ASTEROID_POINTS: u64 = 50;
+const LARGE_ASTEROID_POINTS: u64 = 20;
+const PLAYER_KILL_POINTS: u64 = 500;
+const POINTS_PER_SECOND_ALIVE: u64 = 10;
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct PlayerScore {
+    pub lives: u32,
+    pub small_asteroid_kills: u32,
+    pub medium_asteroid_kills: u32,
+    pub large_asteroid_kills: u32,
+    pub player_kills: u32,
+    pub deaths: u32,
+    /// The total time the player spent alive
+    pub survival_time: Duration,
+}
+
+impl PlayerScore {
+    pub fn new() -> PlayerScore {
+        return PlayerScore { 
+            lives: START_LIVES, 
+            small_asteroid_kills: 0, 
+            medium_asteroid_kills: 0, 
+            large_asteroid_kills: 0, 
+            player_kills: 0, 
+            deaths: 0, 
+            survival_time: Duration::ZERO,
+        };
+    }
+
+    /// The points are derived from the stats so they can't drift apart
+    pub fn score(&self) -> u64 {
+        return self.small_asteroid_kills as u64 * SMALL_ASTEROID_POINTS
+            + self.medium_asteroid_kills as u64 * MEDIUM_ASTEROID_POINTS
+            + self.large_asteroid_kills as u64 * LARGE_ASTEROID_POINTS
+            + self.player_kills as u64 * PLAYER_KILL_POINTS
+            + self.survival_time.as_secs() * POINTS_PER_SECOND_ALIVE;
+    }
+
+    pub fn asteroid_kills(&self) -> u32 {
+        return self.small_asteroid_kills + self.medium_asteroid_kills + self.large_asteroid_kills;
+    }
+}
+
+impl Default for PlayerScore {
+    fn default() -> Self {
+        return PlayerScore::new();
+    }
+}
+
+/// The scores of the session by player id, in the order the players joined
+/// The scores of the players that left are kept
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Scoreboard {
+    scores: Vec<(EntityId, PlayerScore)>,
+}
+
+impl Scoreboard {
+    pub fn new() -> Scoreboard {
+        return Scoreboard { scores: Vec::new() };
+    }
+
+    pub fn get(&self, player: EntityId) -> Option<&PlayerScore> {
+        return self.scores.iter().find(|(id, _)| *id == player).map(|(_, score)| score);
+    }
+
+    /// The score of a player, created if the player doesn't have one yet
+    pub fn get_mut(&mut self, player: EntityId) -> &mut PlayerScore {
+        let idx = match self.scores.iter().position(|(id, _)| *id == player) {
+            Some(idx) => idx,
+            None => {
+                self.scores.push((player, PlayerScore::new()));
+                self.scores.len() - 1
+            },
+        };
+
+        return &mut self.scores[idx].1;
+    }
+
+    pub fn scores(&self) -> &[(EntityId, PlayerScore)] {
+        return &self.scores;
+    }
+
+    /// The players from the best score to the worst
+    pub fn ranking(&self) -> Vec<(EntityId, &PlayerScore)> {
+        let mut output: Vec<_> = self.scores.iter().map(|(id, score)| (*id, score)).collect();
+        output.sort_by_key(|(_, score)| std::cmp::Reverse(score.score()));
+
+        return output;
+    }
+
+    pub fn record_asteroid_kill(&mut self, player: EntityId, size: AsteroidSize) {
+        let score = self.get_mut(player);
+
+        match size {
+            AsteroidSize::Small => score.small_asteroid_kills += 1,
+            AsteroidSize::Medium => score.medium_asteroid_kills += 1,
+            AsteroidSize::Large => score.large_asteroid_kills += 1,
+        }
+    }
+
+    pub fn record_player_kill(&mut self, killer: EntityId) {
+        self.get_mut(killer).player_kills += 1;
+    }
+
+    /// Takes a life from the player, returns the lives left
+    pub fn record_death(&mut self, player: EntityId) -> u32 {
+        let score = self.get_mut(player);
+        score.deaths += 1;
+        score.lives = score.lives.saturating_sub(1);
+
+        return score.lives;
+    }
+
+    pub fn add_survival_time(&mut self, player: EntityId, time: Duration) {
+        self.get_mut(player).survival_time += time;
+    }
+}
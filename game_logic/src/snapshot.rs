@@ -0,0 +1,158 @@
+use std::fmt;
+use std::path::Path;
+use std::time::Duration;
+
+use rand_chacha::ChaCha8Rng;
+use serde::{Serialize, Deserialize};
+
+use super::{WorldConfig, Player, Asteroid, Projectile, Scoreboard, PlayerInput, CollisionEvent, EntityAllocator, StoredChunk};
+
+/// Bumped every time the content of a snapshot changes, old snapshots are refused
+pub const SNAPSHOT_VERSION: u32 = 4;
+
+/// The first bytes of a binary snapshot file, to tell it apart from a json one
+const BINARY_MAGIC: &[u8; 4] = b"ASTW";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SnapshotFormat {
+    /// Readable, for debugging
+    Json,
+    /// Compact, for quicksaves and server restarts
+    Binary,
+}
+
+#[derive(Debug)]
+pub enum SnapshotError {
+    Io(std::io::Error),
+    Json(serde_json::Error),
+    Binary(bincode::Error),
+    /// The snapshot was made by another version of the game
+    Version {
+        found: u32,
+    },
+}
+
+impl fmt::Display for SnapshotError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        return match self {
+            SnapshotError::Io(err) => write!(f, "Unable to access the snapshot file: {}", err),
+            SnapshotError::Json(err) => write!(f, "Invalid json snapshot: {}", err),
+            SnapshotError::Binary(err) => write!(f, "Invalid binary snapshot: {}", err),
+            SnapshotError::Version { found } => write!(f, "Snapshot version {} is not supported, expected {}", found, SNAPSHOT_VERSION),
+        };
+    }
+}
+
+impl std::error::Error for SnapshotError {}
+
+impl From<std::io::Error> for SnapshotError {
+    fn from(err: std::io::Error) -> Self {
+        return SnapshotError::Io(err);
+    }
+}
+
+impl From<serde_json::Error> for SnapshotError {
+    fn from(err: serde_json::Error) -> Self {
+        return SnapshotError::Json(err);
+    }
+}
+
+impl From<bincode::Error> for SnapshotError {
+    fn from(err: bincode::Error) -> Self {
+        return SnapshotError::Binary(err);
+    }
+}
+
+/// Everything needed to restore a world exactly as it was, made by `World::snapshot`
+/// The clock isn't part of it, a restored world runs on a new `RealClock` until told otherwise
+/// The lookups and the spatial index are rebuilt on load
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WorldSnapshot {
+    pub version: u32,
+
+    pub config: WorldConfig,
+    pub seed: u64,
+    pub rng: ChaCha8Rng,
+    pub tick: u64,
+    pub time: Duration,
+    pub accumulator: Duration,
+
+    pub n_player_img: i32,
+    pub n_asteroid_img: i32,
+
+    pub players: Vec<Player>,
+    pub asteroids: Vec<Asteroid>,
+    pub projectiles: Vec<Projectile>,
+    pub entities: EntityAllocator,
+    /// The generated chunks with their expected amount of asteroids
+    pub chunks: Vec<((i64, i64), usize)>,
+    /// The unloaded chunks kept in memory, the oldest first, the ones written to disk aren't part of it
+    pub stored_chunks: Vec<((i64, i64), StoredChunk)>,
+
+    pub scoreboard: Scoreboard,
+    pub pending_inputs: Vec<PlayerInput>,
+    /// Sorted by player
+    pub active_inputs: Vec<PlayerInput>,
+    pub collisions: Vec<CollisionEvent>,
+}
+
+impl WorldSnapshot {
+    pub fn to_json(&self) -> Result<String, SnapshotError> {
+        return Ok(serde_json::to_string_pretty(self)?);
+    }
+
+    pub fn from_json(json: &str) -> Result<WorldSnapshot, SnapshotError> {
+        let value: serde_json::Value = serde_json::from_str(json)?;
+        // Checked first, the rest of a snapshot of another version may not even parse
+        check_version(value["version"].as_u64().unwrap_or(0) as u32)?;
+
+        return Ok(serde_json::from_value(value)?);
+    }
+
+    pub fn to_bytes(&self) -> Result<Vec<u8>, SnapshotError> {
+        let mut output = BINARY_MAGIC.to_vec();
+        bincode::serialize_into(&mut output, self)?;
+
+        return Ok(output);
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<WorldSnapshot, SnapshotError> {
+        let bytes = bytes.strip_prefix(BINARY_MAGIC.as_slice()).unwrap_or(bytes);
+
+        // The version is the first field, so it can be read alone
+        check_version(bincode::deserialize(bytes)?)?;
+
+        return Ok(bincode::deserialize(bytes)?);
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>, format: SnapshotFormat) -> Result<(), SnapshotError> {
+        let bytes = match format {
+            SnapshotFormat::Json => self.to_json()?.into_bytes(),
+            SnapshotFormat::Binary => self.to_bytes()?,
+        };
+
+        std::fs::write(path, bytes)?;
+
+        return Ok(());
+    }
+
+    /// Loads a snapshot saved in any format, the format is recognised from the content of the file
+    pub fn load(path: impl AsRef<Path>) -> Result<WorldSnapshot, SnapshotError> {
+        let bytes = std::fs::read(path)?;
+
+        if bytes.starts_with(BINARY_MAGIC) {
+            return WorldSnapshot::from_bytes(&bytes);
+        }
+
+        let json = String::from_utf8_lossy(&bytes);
+        return WorldSnapshot::from_json(&json);
+    }
+}
+
+fn check_version(found: u32) -> Result<(), SnapshotError> {
+    if found != SNAPSHOT_VERSION {
+        return Err(SnapshotError::Version { found });
+    }
+
+    return Ok(());
+}
@@ -0,0 +1,139 @@
+use cgmath::{Point2, MetricSpace};
+use fnv::FnvHashMap as HashMap;
+
+use super::WorldConfig;
+
+/// Buckets entities by chunk so that the ones around a point can be found without scanning all of them
+/// Entities are referred to by their index in the Vec they are stored in
+#[derive(Debug, Clone)]
+pub struct SpatialIndex {
+    /// The side of a cell, the chunk size of the world
+    cell_size: f64,
+    cells: HashMap<(i64, i64), Vec<usize>>,
+    /// The position and the cell of every entity, indexed like the entities themselves
+    entries: Vec<(Point2<f64>, (i64, i64))>,
+}
+
+impl SpatialIndex {
+    pub fn new(cell_size: f64) -> SpatialIndex {
+        return SpatialIndex { cell_size, cells: HashMap::default(), entries: Vec::new() };
+    }
+
+    /// The cell containing `pos`
+    fn cell_of(&self, pos: Point2<f64>) -> (i64, i64) {
+        return ((pos.x / self.cell_size).floor() as i64, (pos.y / self.cell_size).floor() as i64);
+    }
+
+    pub fn len(&self) -> usize {
+        return self.entries.len();
+    }
+
+    pub fn is_empty(&self) -> bool {
+        return self.entries.is_empty();
+    }
+
+    /// Forgets everything and indexes the positions again
+    /// Must be used when entities were removed, as the indices of the others moved
+    pub fn rebuild<I>(&mut self, positions: I) where I: IntoIterator<Item = Point2<f64>> {
+        for cell in self.cells.values_mut() {
+            cell.clear();
+        }
+        self.entries.clear();
+
+        for (idx, pos) in positions.into_iter().enumerate() {
+            let cell = self.cell_of(pos);
+            self.cells.entry(cell).or_default().push(idx);
+            self.entries.push((pos, cell));
+        }
+
+        self.cells.retain(|_, cell| !cell.is_empty());
+    }
+
+    /// Follows the entities as they move, only the ones that changed chunk are moved in the index
+    /// Entities appended since the last call are added, but removals need a `rebuild`
+    pub fn update<I>(&mut self, positions: I) where I: IntoIterator<Item = Point2<f64>>, I::IntoIter: ExactSizeIterator {
+        let positions = positions.into_iter();
+
+        if positions.len() < self.entries.len() {
+            self.rebuild(positions);
+            return;
+        }
+
+        for (idx, pos) in positions.enumerate() {
+            let new_cell = self.cell_of(pos);
+
+            if idx == self.entries.len() {
+                self.cells.entry(new_cell).or_default().push(idx);
+                self.entries.push((pos, new_cell));
+                continue;
+            }
+
+            let (old_pos, old_cell) = &mut self.entries[idx];
+            *old_pos = pos;
+
+            if *old_cell != new_cell {
+                if let Some(cell) = self.cells.get_mut(old_cell) {
+                    if let Some(in_cell_idx) = cell.iter().position(|x| *x == idx) {
+                        cell.swap_remove(in_cell_idx);
+                    }
+                    if cell.is_empty() {
+                        self.cells.remove(old_cell);
+                    }
+                }
+
+                self.cells.entry(new_cell).or_default().push(idx);
+                *old_cell = new_cell;
+            }
+        }
+    }
+
+    /// Every entity at most `radius` away from `center`, sorted
+    /// Sorted so the result doesn't depend on the history of the index, a rebuilt index gives the same simulation
+    pub fn query_radius(&self, center: Point2<f64>, radius: f64) -> Vec<usize> {
+        let min = Point2 { x: center.x - radius, y: center.y - radius };
+        let max = Point2 { x: center.x + radius, y: center.y + radius };
+        let radius2 = radius * radius;
+
+        let mut output = Vec::new();
+
+        self.for_each_in_cells(min, max, |idx, pos| {
+            if pos.distance2(center) <= radius2 {
+                output.push(idx);
+            }
+        });
+        output.sort_unstable();
+
+        return output;
+    }
+
+    /// The chunks containing at least an entity, with the entities inside of them
+    pub fn cells(&self) -> impl Iterator<Item = (&(i64, i64), &Vec<usize>)> {
+        return self.cells.iter();
+    }
+
+    /// The entities inside a chunk
+    pub fn cell(&self, cell: (i64, i64)) -> &[usize] {
+        return self.cells.get(&cell).map_or(&[], |idxs| idxs.as_slice());
+    }
+
+    fn for_each_in_cells<F>(&self, min: Point2<f64>, max: Point2<f64>, mut f: F) where F: FnMut(usize, Point2<f64>) {
+        let min_cell = self.cell_of(min);
+        let max_cell = self.cell_of(max);
+
+        for x in min_cell.0..=max_cell.0 {
+            for y in min_cell.1..=max_cell.1 {
+                if let Some(cell) = self.cells.get(&(x, y)) {
+                    for idx in cell {
+                        f(*idx, self.entries[*idx].0);
+                    }
+                }
+            }
+        }
+    }
+}
+
+impl Default for SpatialIndex {
+    fn default() -> Self {
+        return SpatialIndex::new(WorldConfig::new().chunk_size);
+    }
+}
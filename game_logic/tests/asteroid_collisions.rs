@@ -0,0 +1,86 @@
+use cgmath::{Point2, Vector2, InnerSpace};
+use proptest::prelude::*;
+
+use game_logic::{Asteroid, AsteroidSize, EntityId};
+use game_logic::collision::{circle_overlap, collide_asteroids, apply_collision_impulse};
+
+fn asteroid(pos: (f64, f64), vel: (f64, f64), rot_speed: f32, size: AsteroidSize) -> Asteroid {
+    return Asteroid {
+        id: EntityId::NONE,
+        pos: Point2 { x: pos.0, y: pos.1 },
+        vel: Vector2 { x: vel.0, y: vel.1 },
+        rot_speed,
+        rot: 0.,
+        img_idx: 0,
+        spawn_time: 0.,
+        size,
+    };
+}
+
+fn momentum(asteroids: &[&Asteroid]) -> Vector2<f64> {
+    return asteroids.iter().fold(Vector2 { x: 0., y: 0. }, |acc, ast| acc + ast.vel * ast.mass());
+}
+
+/// Around the origin, orbital plus spin
+fn angular_momentum(asteroids: &[&Asteroid]) -> f64 {
+    return asteroids.iter().map(|ast| {
+        let p = ast.vel * ast.mass();
+        ast.pos.x * p.y - ast.pos.y * p.x + ast.inertia() * ast.rot_speed as f64
+    }).sum();
+}
+
+fn size() -> impl Strategy<Value = AsteroidSize> {
+    return prop_oneof![Just(AsteroidSize::Small), Just(AsteroidSize::Medium), Just(AsteroidSize::Large)];
+}
+
+/// Two asteroids of any size overlapping by a random amount, with random velocities and spins
+fn overlapping_pair() -> impl Strategy<Value = (Asteroid, Asteroid)> {
+    return (
+        -10.0..10.0f64, -10.0..10.0f64, 0.0..std::f64::consts::TAU, 0.05..0.99f64,
+        -0.5..0.5f64, -0.5..0.5f64, -0.5..0.5f64, -0.5..0.5f64,
+        -2.0..2.0f32, -2.0..2.0f32, size(), size(),
+    ).prop_map(|(x, y, angle, dist, vax, vay, vbx, vby, rot_a, rot_b, size_a, size_b)| {
+        let a = asteroid((x, y), (vax, vay), rot_a, size_a);
+        let mut b = asteroid((x, y), (vbx, vby), rot_b, size_b);
+
+        let dist = dist * (a.radius() + b.radius());
+        b.pos.x += angle.cos() * dist;
+        b.pos.y += angle.sin() * dist;
+
+        (a, b)
+    });
+}
+
+proptest! {
+    #[test]
+    fn collision_conserves_momentum((mut a, mut b) in overlapping_pair()) {
+        let before = momentum(&[&a, &b]);
+
+        let contact = circle_overlap(a.pos, a.radius(), b.pos, b.radius()).unwrap();
+        collide_asteroids(&mut a, &mut b, contact);
+
+        let after = momentum(&[&a, &b]);
+        prop_assert!((before - after).magnitude() <= 1e-12, "{:?} != {:?}", before, after);
+    }
+
+    #[test]
+    fn collision_conserves_angular_momentum((mut a, mut b) in overlapping_pair()) {
+        let before = angular_momentum(&[&a, &b]);
+
+        let contact = circle_overlap(a.pos, a.radius(), b.pos, b.radius()).unwrap();
+        apply_collision_impulse(&mut a, &mut b, contact);
+
+        let after = angular_momentum(&[&a, &b]);
+        // The spins are stored as f32
+        prop_assert!((before - after).abs() <= 1e-6 * before.abs().max(1e-3), "{} != {}", before, after);
+    }
+
+    #[test]
+    fn collision_separates_asteroids((mut a, mut b) in overlapping_pair()) {
+        let contact = circle_overlap(a.pos, a.radius(), b.pos, b.radius()).unwrap();
+        collide_asteroids(&mut a, &mut b, contact);
+
+        prop_assert!((b.vel - a.vel).dot(contact.normal) >= -1e-12);
+        prop_assert!(circle_overlap(a.pos, a.radius(), b.pos, b.radius()).is_none_or(|c| c.depth < 1e-9));
+    }
+}
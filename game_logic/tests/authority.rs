@@ -0,0 +1,85 @@
+use game_logic::{World, WorldConfig, PlayerInput, CollisionEvent};
+
+mod common;
+
+use common::empty_world;
+
+#[test]
+fn worlds_without_authority_leave_the_chunks_alone() {
+    let mut world = World::new(3, 3, 8, WorldConfig::new());
+    world.set_authority(false);
+    let ids: Vec<_> = world.asteroids.iter().map(|ast| ast.id).collect();
+    world.remove_asteroids(&ids);
+
+    for _ in 0..10 {
+        world.step();
+        assert!(world.changed_chunks().is_empty());
+    }
+    assert!(world.asteroids.is_empty());
+
+    world.set_authority(true);
+    world.step();
+    assert!(!world.asteroids.is_empty());
+    assert!(!world.changed_chunks().is_empty());
+}
+
+#[test]
+fn projectiles_stop_on_asteroids_the_world_cant_destroy() {
+    let (mut world, player) = empty_world(21);
+    world.set_authority(false);
+    world.asteroids[0].pos = cgmath::Point2 { x: 0.3, y: 0. };
+    world.asteroids[0].vel = cgmath::Vector2 { x: 0., y: 0. };
+    let target = world.asteroids[0].id;
+
+    world.queue_input(PlayerInput { tick: world.tick() + 1, player, fire: true, ..Default::default() });
+    world.step();
+    world.queue_input(PlayerInput { tick: world.tick() + 1, player, fire: false, ..Default::default() });
+
+    for _ in 0..60 {
+        world.step();
+        assert!(!world.collisions().iter().any(|event| matches!(event, CollisionEvent::ProjectileAsteroid { .. })));
+    }
+
+    assert!(world.asteroid(target).is_some());
+    assert_eq!(world.asteroids.len(), 1);
+    assert!(world.projectiles.is_empty());
+}
+
+#[test]
+fn destroyed_asteroids_change_their_chunk() {
+    let (mut world, _) = empty_world(4);
+    world.asteroids[0].pos = cgmath::Point2 { x: 0.5, y: 0.5 };
+    world.asteroids[0].vel = cgmath::Vector2 { x: 0., y: 0. };
+    world.step();
+    let ast = world.asteroids[0];
+    let chunk = world.config().chunk_of(ast.pos);
+    assert!(world.chunk_asteroids(chunk).iter().any(|other| other.id == ast.id));
+
+    // Once every chunk in range is loaded, nothing changes but the motion
+    for _ in 0..20 {
+        world.step();
+    }
+    assert!(world.changed_chunks().is_empty());
+    assert!(world.active_chunks().contains(&chunk));
+
+    world.destroy_asteroid(ast.id);
+    assert!(world.changed_chunks().contains(&chunk));
+}
+
+#[test]
+fn added_and_removed_asteroids_keep_their_lookups() {
+    let (mut world, _) = empty_world(4);
+    let mut ast = world.asteroids[0];
+    ast.pos = cgmath::Point2 { x: 1., y: 1. };
+
+    let id = world.add_asteroid(ast);
+    assert_ne!(id, ast.id);
+    assert_eq!(world.asteroid(id).unwrap().pos, ast.pos);
+
+    world.asteroid_mut(id).unwrap().pos.x = 2.;
+    world.remove_asteroids(&[ast.id]);
+
+    assert!(world.asteroid(ast.id).is_none());
+    assert_eq!(world.asteroid(id).unwrap().pos.x, 2.);
+    assert_eq!(world.asteroids.len(), 1);
+}
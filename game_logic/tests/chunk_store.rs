@@ -0,0 +1,149 @@
+use game_logic::{World, WorldConfig, DensityConfig, WorldSnapshot};
+
+const SEED: u64 = 11;
+
+/// A chunk near the spawn, away from the asteroid every world starts with
+const CHUNK: (i64, i64) = (-3, -3);
+
+/// Asteroids that barely move, so they stay in the chunk they were spawned in
+fn still_config() -> WorldConfig {
+    return WorldConfig { density: DensityConfig::Uniform { per_chunk: 3. }, ast_speed_max: 1e-20, ..WorldConfig::new() };
+}
+
+fn move_players(world: &mut World, pos: (f64, f64)) {
+    for id in world.player_ids() {
+        world.player_mut(id).unwrap().pos = cgmath::Point2 { x: pos.0, y: pos.1 };
+    }
+}
+
+fn n_ast_in(world: &World, chunk: (i64, i64)) -> usize {
+    return world.asteroids.iter().filter(|ast| world.config().chunk_of(ast.pos) == chunk).count();
+}
+
+/// Steps until the chunks around the players are all loaded and the others unloaded
+fn settle(world: &mut World) {
+    for _ in 0..20 {
+        world.step();
+    }
+}
+
+/// Destroys the asteroids of `CHUNK` and their fragments until it is empty
+fn clear_chunk(world: &mut World) {
+    for _ in 0..100 {
+        let id = match world.asteroids.iter().find(|ast| world.config().chunk_of(ast.pos) == CHUNK) {
+            Some(ast) => ast.id,
+            None => return,
+        };
+
+        world.destroy_asteroid(id);
+    }
+
+    panic!("the chunk never got empty");
+}
+
+/// Leaves the spawn for long enough to unload it, then comes back
+fn leave_and_return(world: &mut World) {
+    move_players(world, (1000., 1000.));
+    settle(world);
+    assert_eq!(n_ast_in(world, CHUNK), 0);
+
+    move_players(world, (0., 0.));
+    settle(world);
+}
+
+#[test]
+fn destroyed_asteroids_stay_destroyed() {
+    let mut world = World::new(3, 3, SEED, still_config());
+    settle(&mut world);
+    assert_eq!(n_ast_in(&world, CHUNK), 3);
+
+    clear_chunk(&mut world);
+    leave_and_return(&mut world);
+
+    assert_eq!(n_ast_in(&world, CHUNK), 0);
+}
+
+#[test]
+fn untouched_chunks_come_back_as_they_were() {
+    let mut world = World::new(3, 3, SEED, still_config());
+    settle(&mut world);
+
+    let mut before: Vec<_> = world.asteroids.iter().map(|ast| (ast.pos.x.to_bits(), ast.pos.y.to_bits(), ast.size as u8)).collect();
+    leave_and_return(&mut world);
+    let mut after: Vec<_> = world.asteroids.iter().map(|ast| (ast.pos.x.to_bits(), ast.pos.y.to_bits(), ast.size as u8)).collect();
+
+    before.sort_unstable();
+    after.sort_unstable();
+    assert_eq!(before, after);
+}
+
+#[test]
+fn store_is_bounded() {
+    let config = WorldConfig { chunk_store_capacity: 50, ..still_config() };
+    let mut world = World::new(3, 3, SEED, config);
+
+    for i in 0..20 {
+        move_players(&mut world, (i as f64 * 100., 0.));
+        world.step();
+
+        assert!(world.asteroid_manager().chunk_store().len() <= 50);
+    }
+}
+
+#[test]
+fn evicted_chunks_are_read_back_from_disk() {
+    let dir = std::env::temp_dir().join(format!("chunk_store_test_{}", std::process::id()));
+    // Too small to hold every chunk around the spawn, the cleared one has to go to disk
+    let config = WorldConfig { chunk_store_capacity: 10, ..still_config() };
+
+    let mut world = World::new(3, 3, SEED, config);
+    world.set_chunk_store_dir(Some(dir.clone()));
+    settle(&mut world);
+    assert_eq!(n_ast_in(&world, CHUNK), 3);
+
+    clear_chunk(&mut world);
+    leave_and_return(&mut world);
+    let _ = std::fs::remove_dir_all(&dir);
+
+    assert_eq!(n_ast_in(&world, CHUNK), 0);
+}
+
+#[test]
+fn chunks_left_on_disk_by_another_world_are_not_read() {
+    let dir = std::env::temp_dir().join(format!("chunk_store_stale_test_{}", std::process::id()));
+    let config = WorldConfig { chunk_store_capacity: 10, ..still_config() };
+
+    let mut first = World::new(3, 3, SEED, config);
+    first.set_chunk_store_dir(Some(dir.clone()));
+    settle(&mut first);
+    clear_chunk(&mut first);
+    move_players(&mut first, (1000., 1000.));
+    settle(&mut first);
+
+    // Same directory and same chunks, but a world of its own
+    let mut second = World::new(3, 3, SEED, config);
+    second.set_chunk_store_dir(Some(dir.clone()));
+    settle(&mut second);
+    let _ = std::fs::remove_dir_all(&dir);
+
+    assert_eq!(n_ast_in(&second, CHUNK), 3);
+}
+
+#[test]
+fn snapshot_keeps_the_stored_chunks() {
+    let mut world = World::new(3, 3, SEED, still_config());
+    settle(&mut world);
+    clear_chunk(&mut world);
+
+    move_players(&mut world, (1000., 1000.));
+    settle(&mut world);
+
+    let bytes = world.snapshot().to_bytes().unwrap();
+    let mut restored = World::from_snapshot(WorldSnapshot::from_bytes(&bytes).unwrap());
+    assert_eq!(restored.state_hash(), world.state_hash());
+
+    move_players(&mut restored, (0., 0.));
+    settle(&mut restored);
+
+    assert_eq!(n_ast_in(&restored, CHUNK), 0);
+}
@@ -0,0 +1,65 @@
+use std::time::Duration;
+
+use game_logic::{World, WorldConfig, Clock, ManualClock, WORLD_UPD_RATE};
+
+/// A clock moved by hand that caps the catching up like the real one
+struct CappedClock {
+    clock: ManualClock,
+}
+
+impl Clock for CappedClock {
+    fn now(&self) -> Duration {
+        return self.clock.now();
+    }
+
+    fn max_ticks_per_update(&self) -> Option<u32> {
+        return Some(10);
+    }
+}
+
+fn ticks_in(elapsed: Duration) -> u64 {
+    return (elapsed.as_nanos() / WORLD_UPD_RATE.as_nanos()) as u64;
+}
+
+#[test]
+fn manual_clock_simulates_all_the_elapsed_time() {
+    let clock = ManualClock::new();
+    let mut world = World::new(3, 3, 5, WorldConfig::new());
+    world.set_clock(Box::new(clock.clone()));
+
+    clock.advance(Duration::from_secs(1));
+    world.update();
+
+    assert_eq!(world.tick(), ticks_in(Duration::from_secs(1)));
+}
+
+#[test]
+fn manual_clock_runs_faster_than_real_time() {
+    let clock = ManualClock::new();
+    let mut world = World::new(3, 3, 5, WorldConfig::new());
+    world.set_clock(Box::new(clock.clone()));
+
+    // A minute of simulation in a handful of updates
+    for _ in 0..6 {
+        clock.advance(Duration::from_secs(10));
+        world.update();
+    }
+
+    assert_eq!(world.tick(), ticks_in(Duration::from_secs(60)));
+}
+
+#[test]
+fn capped_clock_drops_the_time_it_cannot_catch_up() {
+    let clock = ManualClock::new();
+    let mut world = World::new(3, 3, 5, WorldConfig::new());
+    world.set_clock(Box::new(CappedClock { clock: clock.clone() }));
+
+    clock.advance(Duration::from_secs(1));
+    world.update();
+    assert_eq!(world.tick(), 10);
+
+    // The dropped time isn't simulated later
+    clock.advance(WORLD_UPD_RATE);
+    world.update();
+    assert_eq!(world.tick(), 11);
+}
@@ -0,0 +1,13 @@
+use game_logic::{World, WorldConfig, DensityConfig, EntityId};
+
+/// A world with a single player at the origin facing +x, and its starting asteroid out of the way
+pub fn empty_world(seed: u64) -> (World, EntityId) {
+    let config = WorldConfig { density: DensityConfig::Uniform { per_chunk: 0. }, ..WorldConfig::new() };
+    let mut world = World::new(3, 3, seed, config);
+
+    let ids = world.player_ids();
+    world.remove_player(ids[1]);
+    world.asteroids[0].pos = cgmath::Point2 { x: 0., y: 50. };
+
+    return (world, ids[0]);
+}
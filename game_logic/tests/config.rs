@@ -0,0 +1,67 @@
+use game_logic::{World, WorldConfig, ConfigError, DensityConfig};
+
+#[test]
+fn missing_fields_take_their_default() {
+    let path = std::env::temp_dir().join(format!("world_config_test_{}.json", std::process::id()));
+    std::fs::write(&path, r#"{ "chunk_load_dist": 3.5, "ast_density": 0.5 }"#).unwrap();
+
+    let config = WorldConfig::load(&path).unwrap();
+    std::fs::remove_file(path).unwrap();
+
+    assert_eq!(config.chunk_load_dist, 3.5);
+    assert_eq!(config.ast_density, 0.5);
+    assert_eq!(config.chunk_size, WorldConfig::new().chunk_size);
+}
+
+#[test]
+fn config_changes_the_generation() {
+    let mut sparse = World::new(3, 3, 5, WorldConfig { ast_density: 0.5, ..WorldConfig::new() });
+    let mut dense = World::new(3, 3, 5, WorldConfig::new());
+    let mut near = World::new(3, 3, 5, WorldConfig { chunk_load_dist: 2., ..WorldConfig::new() });
+
+    sparse.step();
+    dense.step();
+    near.step();
+
+    assert!(sparse.asteroids.len() < dense.asteroids.len());
+    assert!(near.asteroids.len() < dense.asteroids.len());
+}
+
+#[test]
+fn the_default_config_is_valid() {
+    assert!(WorldConfig::new().validate().is_ok());
+}
+
+#[test]
+fn configs_the_simulation_cant_run_are_refused() {
+    let noise = |void_level, octaves| DensityConfig::Noise { max_per_chunk: 8., scale: 12., void_level, octaves };
+
+    let invalid = [
+        WorldConfig { chunk_size: 0., ..WorldConfig::new() },
+        WorldConfig { chunk_size: f64::NAN, ..WorldConfig::new() },
+        WorldConfig { chunk_load_dist: 1e300, chunk_unload_dist: 1e300, ..WorldConfig::new() },
+        WorldConfig { chunk_load_dist: 8., chunk_unload_dist: 6., ..WorldConfig::new() },
+        WorldConfig { chunk_unload_dist: f64::INFINITY, ..WorldConfig::new() },
+        WorldConfig { chunks_per_tick: 0, ..WorldConfig::new() },
+        WorldConfig { density: noise(1., 3), ..WorldConfig::new() },
+        WorldConfig { density: noise(0.5, u32::MAX), ..WorldConfig::new() },
+        WorldConfig { density: DensityConfig::Uniform { per_chunk: -1. }, ..WorldConfig::new() },
+    ];
+
+    for config in invalid {
+        assert!(config.validate().is_err(), "{:?}", config);
+    }
+}
+
+#[test]
+fn invalid_files_are_not_loaded() {
+    let path = std::env::temp_dir().join(format!("world_config_invalid_test_{}.json", std::process::id()));
+    std::fs::write(&path, r#"{ "chunk_size": 0 }"#).unwrap();
+
+    let result = WorldConfig::load(&path);
+    let fallback = WorldConfig::load_or_default(&path);
+    std::fs::remove_file(path).unwrap();
+
+    assert!(matches!(result, Err(ConfigError::Invalid(_))));
+    assert_eq!(fallback, WorldConfig::new());
+}
@@ -0,0 +1,102 @@
+use game_logic::{DensityModel, DensityConfig, NoiseDensity, UniformDensity, World, WorldConfig};
+
+const SEED: u64 = 42;
+
+/// The densities of a square of `side` chunks starting at `origin`
+fn sample(model: &dyn DensityModel, seed: u64, origin: (i64, i64), side: i64) -> Vec<f64> {
+    let mut output = Vec::new();
+
+    for x in origin.0..origin.0 + side {
+        for y in origin.1..origin.1 + side {
+            output.push(model.density(seed, (x, y)));
+        }
+    }
+
+    return output;
+}
+
+fn mean(values: &[f64]) -> f64 {
+    return values.iter().sum::<f64>() / values.len() as f64;
+}
+
+fn noise() -> NoiseDensity {
+    return NoiseDensity { max_per_chunk: 8., scale: 12., void_level: 0.5, octaves: 3 };
+}
+
+#[test]
+fn noise_stays_within_bounds() {
+    let model = noise();
+
+    for value in sample(&model, SEED, (-100, -100), 200) {
+        assert!((0. ..=model.max_per_chunk).contains(&value));
+    }
+}
+
+#[test]
+fn noise_has_voids_and_clusters() {
+    let values = sample(&noise(), SEED, (-100, -100), 200);
+    let n = values.len() as f64;
+
+    let voids = values.iter().filter(|value| value.round() == 0.).count() as f64 / n;
+    let clusters = values.iter().filter(|value| **value >= 6.).count() as f64 / n;
+
+    assert!(voids > 0.1, "only {:.2} of the chunks are empty", voids);
+    assert!(clusters > 0.1, "only {:.2} of the chunks are dense", clusters);
+    assert!(voids + clusters < 0.8, "the field is all or nothing");
+}
+
+#[test]
+fn noise_is_coherent() {
+    let model = noise();
+
+    // Neighbouring chunks must be much closer in density than chunks taken far apart
+    let mut neighbour_diff = 0.;
+    let mut far_diff = 0.;
+    let mut n = 0.;
+
+    for x in -50..50 {
+        for y in -50..50 {
+            let here = model.density(SEED, (x, y));
+            neighbour_diff += (here - model.density(SEED, (x + 1, y))).abs();
+            far_diff += (here - model.density(SEED, (x + 97, y - 61))).abs();
+            n += 1.;
+        }
+    }
+
+    assert!(neighbour_diff / n < far_diff / n * 0.5);
+}
+
+#[test]
+fn density_doesnt_grow_with_distance() {
+    let model = noise();
+
+    let near = mean(&sample(&model, SEED, (-50, -50), 100));
+    let far = mean(&sample(&model, SEED, (10_000, -20_000), 100));
+
+    assert!((near - far).abs() < near * 0.25, "near {:.2}, far {:.2}", near, far);
+}
+
+#[test]
+fn density_only_depends_on_the_seed_and_the_chunk() {
+    let model = noise();
+
+    assert_eq!(sample(&model, SEED, (-20, -20), 40), sample(&model, SEED, (-20, -20), 40));
+    assert_ne!(sample(&model, SEED, (-20, -20), 40), sample(&model, SEED + 1, (-20, -20), 40));
+}
+
+#[test]
+fn uniform_density_is_uniform() {
+    let model = UniformDensity { per_chunk: 3. };
+
+    assert!(sample(&model, SEED, (-30, -30), 60).iter().all(|value| *value == 3.));
+}
+
+#[test]
+fn world_follows_its_density_model() {
+    let config = WorldConfig { density: DensityConfig::Uniform { per_chunk: 2. }, ..WorldConfig::new() };
+    let mut world = World::new(3, 3, SEED, config);
+    world.step();
+
+    let chunks = world.asteroid_manager().chunks();
+    assert!(chunks.iter().all(|(_, n)| *n == 2));
+}
@@ -0,0 +1,73 @@
+use std::collections::HashSet;
+
+use game_logic::{World, WorldConfig, EntityAllocator, EntityId, Player};
+
+#[test]
+fn freed_slots_come_back_with_a_new_generation() {
+    let mut allocator = EntityAllocator::new();
+    let first = allocator.alloc();
+    let second = allocator.alloc();
+
+    allocator.free(first);
+    assert!(!allocator.is_alive(first));
+    assert!(allocator.is_alive(second));
+
+    let reused = allocator.alloc();
+    assert_eq!(reused.index(), first.index());
+    assert_ne!(reused, first);
+    assert!(allocator.is_alive(reused));
+    assert!(!allocator.is_alive(first));
+}
+
+#[test]
+fn freeing_twice_does_not_hand_out_a_slot_twice() {
+    let mut allocator = EntityAllocator::new();
+    let id = allocator.alloc();
+
+    allocator.free(id);
+    allocator.free(id);
+
+    assert_ne!(allocator.alloc(), allocator.alloc());
+}
+
+#[test]
+fn removed_players_ids_are_never_reused() {
+    let mut world = World::new(3, 3, 2, WorldConfig::new());
+    let mut seen: HashSet<EntityId> = world.player_ids().into_iter().collect();
+
+    for _ in 0..20 {
+        let old = world.player_ids()[0];
+        world.remove_player(old);
+        assert!(world.player(old).is_none());
+
+        let new = world.add_player(Player::new());
+        assert!(seen.insert(new), "{} was handed out twice", new);
+        assert!(world.player(old).is_none());
+        assert!(world.player(new).is_some());
+
+        world.step();
+    }
+}
+
+#[test]
+fn ids_of_destroyed_asteroids_stop_resolving() {
+    let mut world = World::new(3, 3, 2, WorldConfig::new());
+    for _ in 0..30 {
+        world.step();
+    }
+
+    let mut destroyed = Vec::new();
+
+    for _ in 0..20 {
+        let id = world.asteroids[0].id;
+        world.destroy_asteroid(id);
+        destroyed.push(id);
+
+        for ast in &world.asteroids {
+            assert!(!ast.id.is_none());
+            assert!(!destroyed.contains(&ast.id));
+        }
+    }
+
+    assert!(destroyed.iter().all(|id| world.asteroid(*id).is_none()));
+}
@@ -0,0 +1,64 @@
+use game_logic::{World, WorldConfig, Asteroid};
+use game_logic::asteroids::spawn_ast_in_chunk;
+
+/// What the simulation sees of an asteroid, bit for bit
+fn gameplay(ast: &Asteroid) -> (u64, u64, u64, u64, u32, u32, u8) {
+    return (
+        ast.pos.x.to_bits(), ast.pos.y.to_bits(),
+        ast.vel.x.to_bits(), ast.vel.y.to_bits(),
+        ast.rot.to_bits(), ast.rot_speed.to_bits(),
+        ast.size as u8,
+    );
+}
+
+fn gen_chunk(n_ast_img: i32, seed: u64, chunk: (i64, i64)) -> Vec<Asteroid> {
+    let mut asteroids = Vec::new();
+    spawn_ast_in_chunk(&mut asteroids, n_ast_img, 20, chunk, 0., seed, &WorldConfig::new());
+
+    return asteroids;
+}
+
+#[test]
+fn same_seed_gives_the_same_chunk() {
+    let first = gen_chunk(3, 42, (4, -7));
+    let second = gen_chunk(3, 42, (4, -7));
+
+    assert_eq!(first.iter().map(gameplay).collect::<Vec<_>>(), second.iter().map(gameplay).collect::<Vec<_>>());
+    assert_eq!(first.iter().map(|ast| ast.img_idx).collect::<Vec<_>>(), second.iter().map(|ast| ast.img_idx).collect::<Vec<_>>());
+}
+
+#[test]
+fn chunks_differ_between_seeds_and_positions() {
+    let chunk = gen_chunk(3, 42, (4, -7));
+
+    assert_ne!(chunk.iter().map(gameplay).collect::<Vec<_>>(), gen_chunk(3, 43, (4, -7)).iter().map(gameplay).collect::<Vec<_>>());
+    assert_ne!(chunk.iter().map(gameplay).collect::<Vec<_>>(), gen_chunk(3, 42, (-7, 4)).iter().map(gameplay).collect::<Vec<_>>());
+}
+
+#[test]
+fn number_of_images_does_not_change_the_chunk() {
+    let three = gen_chunk(3, 42, (4, -7));
+    let five = gen_chunk(5, 42, (4, -7));
+
+    assert_eq!(three.iter().map(gameplay).collect::<Vec<_>>(), five.iter().map(gameplay).collect::<Vec<_>>());
+}
+
+#[test]
+fn worlds_with_the_same_seed_generate_the_same_asteroids() {
+    // Like a server and a client that have different sprites
+    let mut server = World::new(3, 3, 77, WorldConfig::new());
+    let mut client = World::new(5, 3, 77, WorldConfig::new());
+
+    for _ in 0..10 {
+        server.step();
+        client.step();
+    }
+
+    let mut server_ast: Vec<_> = server.asteroids.iter().map(gameplay).collect();
+    let mut client_ast: Vec<_> = client.asteroids.iter().map(gameplay).collect();
+    server_ast.sort_unstable();
+    client_ast.sort_unstable();
+
+    assert!(!server_ast.is_empty());
+    assert_eq!(server_ast, client_ast);
+}
@@ -0,0 +1,80 @@
+use std::time::Duration;
+
+use game_logic::{World, Player, PlayerState};
+
+mod common;
+
+use common::empty_world;
+
+/// Steps until `done` or panics after `max` of simulated time
+fn step_until<F>(world: &mut World, max: Duration, done: F) where F: Fn(&World) -> bool {
+    let end = world.time() + max;
+
+    while !done(world) {
+        assert!(world.time() < end, "timed out");
+        world.step();
+    }
+}
+
+#[test]
+fn damage_kills_at_zero_health() {
+    let mut player = Player::new();
+    let now = Duration::from_secs(10);
+
+    assert!(!player.damage(Player::MAX_HEALTH / 2., now));
+    assert_eq!(player.health, Player::MAX_HEALTH / 2.);
+    assert!(player.is_alive());
+
+    assert!(player.damage(Player::MAX_HEALTH, now));
+    assert_eq!(player.health, 0.);
+    assert!(matches!(player.state, PlayerState::Dead { respawn_time } if respawn_time > now));
+
+    // A dead player can't be killed again
+    assert!(!player.damage(Player::MAX_HEALTH, now));
+}
+
+#[test]
+fn respawn_gives_a_window_of_invulnerability() {
+    let mut player = Player::new();
+    let now = Duration::from_secs(10);
+    player.damage(Player::MAX_HEALTH, now);
+
+    player.respawn(cgmath::Point2 { x: 1., y: 1. }, now);
+    assert!(player.is_alive());
+    assert_eq!(player.health, Player::MAX_HEALTH);
+    assert!(player.is_invulnerable(now));
+
+    assert!(!player.damage(Player::MAX_HEALTH, now + Duration::from_millis(1)));
+    assert_eq!(player.health, Player::MAX_HEALTH);
+
+    let vulnerable = player.invulnerable_until;
+    assert!(vulnerable > now);
+    assert!(!player.is_invulnerable(vulnerable));
+    assert!(player.damage(Player::MAX_HEALTH, vulnerable));
+}
+
+#[test]
+fn dead_players_respawn_invulnerable_then_become_vulnerable() {
+    let (mut world, id) = empty_world(4);
+    world.step();
+
+    let now = world.time();
+    assert!(world.player_mut(id).unwrap().damage(Player::MAX_HEALTH, now));
+
+    world.step();
+    assert!(!world.player(id).unwrap().is_alive());
+
+    step_until(&mut world, Duration::from_secs(10), |world| world.player(id).unwrap().is_alive());
+    assert!(world.time() > now);
+    assert!(world.player(id).unwrap().is_invulnerable(world.time()));
+
+    let now = world.time();
+    assert!(!world.player_mut(id).unwrap().damage(Player::MAX_HEALTH, now));
+
+    step_until(&mut world, Duration::from_secs(10), |world| !world.player(id).unwrap().is_invulnerable(world.time()));
+
+    let now = world.time();
+    let player = world.player_mut(id).unwrap();
+    assert!(!player.damage(Player::MAX_HEALTH / 2., now));
+    assert_eq!(player.health, Player::MAX_HEALTH / 2.);
+}
@@ -0,0 +1,111 @@
+use cgmath::{Point2, Vector2, MetricSpace};
+
+use game_logic::{World, Player, CollisionEvent, EntityId};
+use game_logic::collision::detect_collisions;
+
+mod common;
+
+use common::empty_world;
+
+/// The player at the origin and a still asteroid on its right, `dist` times the distance at which they touch,
+/// already in the spatial index
+fn world_with_asteroid(dist: f64) -> (World, EntityId, EntityId) {
+    let (mut world, player) = empty_world(9);
+    let x = dist * (world.player(player).unwrap().radius() + world.asteroids[0].radius());
+    world.asteroids[0].pos = Point2 { x, y: 0. };
+    world.asteroids[0].vel = Vector2 { x: 0., y: 0. };
+    world.asteroids[0].rot_speed = 0.;
+    let asteroid = world.asteroids[0].id;
+
+    // Indexes the asteroid where it is now, the tick may already have pushed the player out of it
+    world.step();
+    world.player_mut(player).unwrap().pos = Point2 { x: 0., y: 0. };
+
+    return (world, player, asteroid);
+}
+
+/// How far apart the player and the asteroid are when they just touch
+fn touching_dist(world: &World, player: EntityId, asteroid: EntityId) -> f64 {
+    return world.player(player).unwrap().radius() + world.asteroid(asteroid).unwrap().radius();
+}
+
+#[test]
+fn overlapping_players_collide() {
+    let (world, player, asteroid) = world_with_asteroid(0.5);
+
+    let events = detect_collisions(&world);
+    assert_eq!(events.len(), 1);
+
+    match events[0] {
+        CollisionEvent::PlayerAsteroid { player: hit_player, asteroid: hit_asteroid, contact } => {
+            assert_eq!((hit_player, hit_asteroid), (player, asteroid));
+            // From the player toward the asteroid
+            assert!((contact.normal.x - 1.).abs() < 1e-9);
+            assert!((contact.depth - touching_dist(&world, player, asteroid) * 0.5).abs() < 1e-9);
+        },
+        event => panic!("{:?}", event),
+    }
+}
+
+#[test]
+fn distant_asteroids_do_not_collide() {
+    let (world, _, _) = world_with_asteroid(1.01);
+    assert!(detect_collisions(&world).is_empty());
+}
+
+#[test]
+fn dead_players_do_not_collide() {
+    let (mut world, player, _) = world_with_asteroid(0.5);
+    let now = world.time();
+    world.player_mut(player).unwrap().damage(Player::MAX_HEALTH, now);
+
+    assert!(detect_collisions(&world).is_empty());
+}
+
+#[test]
+fn ramming_an_asteroid_bounces_and_damages() {
+    let (mut world, player, asteroid) = world_with_asteroid(3.);
+    world.player_mut(player).unwrap().vel = Vector2 { x: 0.5, y: 0. };
+
+    while world.collisions().is_empty() {
+        assert!(world.tick() < 60, "the player never hit the asteroid");
+        world.step();
+    }
+
+    assert!(matches!(world.collisions()[0], CollisionEvent::PlayerAsteroid { player: hit, .. } if hit == player));
+
+    let hit = *world.player(player).unwrap();
+    assert!(hit.vel.x < 0.);
+    assert!(hit.health < Player::MAX_HEALTH);
+    // Pushed out of the asteroid
+    let ast_pos = world.asteroid(asteroid).unwrap().pos;
+    assert!(hit.pos.distance(ast_pos) >= touching_dist(&world, player, asteroid) - 1e-9);
+}
+
+#[test]
+fn players_moving_away_are_only_pushed_out() {
+    let (mut world, player, asteroid) = world_with_asteroid(0.5);
+    world.player_mut(player).unwrap().vel = Vector2 { x: -0.5, y: 0. };
+
+    world.step();
+
+    assert_eq!(world.collisions().len(), 1);
+    let pushed = *world.player(player).unwrap();
+    assert_eq!(pushed.health, Player::MAX_HEALTH);
+    assert_eq!(pushed.vel.x, -0.5);
+    assert!(pushed.pos.distance(world.asteroid(asteroid).unwrap().pos) >= touching_dist(&world, player, asteroid) - 1e-9);
+}
+
+#[test]
+fn ramming_too_fast_kills() {
+    let (mut world, player, _) = world_with_asteroid(3.);
+    world.player_mut(player).unwrap().vel = Vector2 { x: 5., y: 0. };
+
+    while world.collisions().is_empty() {
+        assert!(world.tick() < 60, "the player never hit the asteroid");
+        world.step();
+    }
+
+    assert!(!world.player(player).unwrap().is_alive());
+    assert_eq!(world.scoreboard().get(player).unwrap().deaths, 1);
+}
@@ -0,0 +1,78 @@
+use game_logic::{World, PlayerInput, EntityId, AsteroidSize};
+
+mod common;
+
+use common::empty_world;
+
+fn set_fire(world: &mut World, player: EntityId, fire: bool) {
+    world.queue_input(PlayerInput { tick: world.tick() + 1, player, fire, ..Default::default() });
+}
+
+#[test]
+fn holding_fire_respects_the_cooldown() {
+    let (mut world, player) = empty_world(21);
+
+    set_fire(&mut world, player, true);
+    world.step();
+    assert_eq!(world.projectiles.len(), 1);
+    assert_eq!(world.projectiles[0].owner, player);
+
+    world.step();
+    assert_eq!(world.projectiles.len(), 1);
+}
+
+#[test]
+fn projectiles_expire_after_their_lifetime() {
+    let (mut world, player) = empty_world(21);
+
+    set_fire(&mut world, player, true);
+    world.step();
+    set_fire(&mut world, player, false);
+
+    let projectile = world.projectiles[0];
+    assert!(projectile.expire_time > world.time());
+
+    while world.time() < projectile.expire_time {
+        assert!(world.projectile(projectile.id).is_some());
+        world.step();
+    }
+
+    assert!(world.projectile(projectile.id).is_none());
+    assert!(world.projectiles.is_empty());
+}
+
+#[test]
+fn projectiles_move_away_from_the_ship() {
+    let (mut world, player) = empty_world(21);
+
+    set_fire(&mut world, player, true);
+    world.step();
+    let start = world.projectiles[0].pos;
+
+    world.step();
+    assert!(world.projectiles[0].pos.x > start.x);
+    assert_eq!(world.projectiles[0].pos.y, start.y);
+}
+
+#[test]
+fn hitting_an_asteroid_splits_it() {
+    let (mut world, player) = empty_world(21);
+    world.asteroids[0].pos = cgmath::Point2 { x: 0.3, y: 0. };
+    world.asteroids[0].vel = cgmath::Vector2 { x: 0., y: 0. };
+    world.asteroids[0].rot_speed = 0.;
+    let target = world.asteroids[0].id;
+    assert_eq!(world.asteroids[0].size, AsteroidSize::Medium);
+
+    set_fire(&mut world, player, true);
+    world.step();
+    set_fire(&mut world, player, false);
+
+    for _ in 0..60 {
+        world.step();
+    }
+
+    assert!(world.asteroid(target).is_none());
+    assert!(world.projectiles.is_empty());
+    assert!(!world.asteroids.is_empty());
+    assert!(world.asteroids.iter().all(|ast| ast.size == AsteroidSize::Small));
+}
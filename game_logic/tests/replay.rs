@@ -0,0 +1,89 @@
+use std::time::Duration;
+
+use game_logic::{World, WorldConfig, ManualClock, PlayerInput, Replay, ReplayError};
+
+/// Records a session driven like the client drives it: a clock with uneven frames and inputs
+/// queued between them
+fn record_session() -> (Replay, u64) {
+    let clock = ManualClock::new();
+    let mut world = World::new(3, 3, 99, WorldConfig::new());
+    world.set_clock(Box::new(clock.clone()));
+
+    let ids = world.player_ids();
+
+    // Warms up before recording, so the replay doesn't start from a fresh world
+    for _ in 0..60 {
+        world.step();
+    }
+    world.queue_input(PlayerInput { tick: world.tick() + 5, player: ids[1], fire: true, ..Default::default() });
+
+    world.start_recording();
+
+    for frame in 0..400u64 {
+        if frame % 50 == 0 {
+            let mut input = PlayerInput::new(world.tick() + 1, ids[0]);
+            input.thrust = frame % 100 == 0;
+            input.turn_left = frame % 150 == 0;
+            input.fire = true;
+            world.queue_input(input);
+        }
+
+        clock.advance(Duration::from_millis(5 + frame % 20));
+        world.update();
+    }
+
+    let hash = world.state_hash();
+    return (world.stop_recording().unwrap(), hash);
+}
+
+#[test]
+fn replay_ends_in_the_recorded_state() {
+    let (replay, hash) = record_session();
+
+    assert!(replay.n_ticks > 0);
+    assert_eq!(replay.end_hash, hash);
+    assert_eq!(replay.verify().unwrap().state_hash(), hash);
+}
+
+#[test]
+fn replay_survives_a_round_trip_to_a_file() {
+    let (replay, hash) = record_session();
+    let path = std::env::temp_dir().join(format!("replay_test_{}.bin", std::process::id()));
+
+    replay.save(&path).unwrap();
+    let loaded = Replay::load(&path).unwrap();
+    std::fs::remove_file(path).unwrap();
+
+    assert_eq!(loaded.verify().unwrap().state_hash(), hash);
+}
+
+#[test]
+fn watched_replay_matches_the_headless_one() {
+    let (replay, hash) = record_session();
+    let mut world = replay.start_world();
+
+    for _ in 0..replay.n_ticks {
+        world.step();
+    }
+
+    assert_eq!(world.state_hash(), hash);
+}
+
+#[test]
+fn verification_reports_every_tick() {
+    let (replay, hash) = record_session();
+    let mut played = Vec::new();
+
+    let world = replay.verify_with_progress(|n| played.push(n)).unwrap();
+
+    assert_eq!(world.state_hash(), hash);
+    assert_eq!(played, (1..=replay.n_ticks).collect::<Vec<_>>());
+}
+
+#[test]
+fn tampered_replay_desyncs() {
+    let (mut replay, _) = record_session();
+    replay.inputs[0].turn_right = !replay.inputs[0].turn_right;
+
+    assert!(matches!(replay.verify(), Err(ReplayError::Desync { .. })));
+}
@@ -0,0 +1,106 @@
+use std::time::Duration;
+
+use game_logic::{World, WorldConfig, DensityConfig, PlayerInput, PlayerState, EntityId, AsteroidSize, Scoreboard};
+use game_logic::score::START_LIVES;
+
+/// Two players on the x axis facing +x, the shooter at the origin and the target in its line of fire
+fn duel_world() -> (World, EntityId, EntityId) {
+    let config = WorldConfig { density: DensityConfig::Uniform { per_chunk: 0. }, ..WorldConfig::new() };
+    let mut world = World::new(3, 3, 6, config);
+    world.asteroids[0].pos = cgmath::Point2 { x: 0., y: 50. };
+
+    let ids = world.player_ids();
+    world.player_mut(ids[1]).unwrap().pos = cgmath::Point2 { x: 0.3, y: 0. };
+
+    return (world, ids[0], ids[1]);
+}
+
+#[test]
+fn killing_a_player_scores_for_the_killer() {
+    let (mut world, shooter, target) = duel_world();
+    world.queue_input(PlayerInput { tick: 1, player: shooter, fire: true, ..Default::default() });
+
+    let end = Duration::from_secs(10);
+    while world.player(target).unwrap().is_alive() {
+        assert!(world.time() < end, "the target never died");
+        world.step();
+    }
+
+    let shooter_score = *world.scoreboard().get(shooter).unwrap();
+    let target_score = *world.scoreboard().get(target).unwrap();
+
+    assert_eq!(shooter_score.player_kills, 1);
+    assert_eq!(shooter_score.deaths, 0);
+    assert_eq!(target_score.deaths, 1);
+    assert_eq!(target_score.lives, START_LIVES - 1);
+    assert!(shooter_score.score() > target_score.score());
+    assert_eq!(world.scoreboard().ranking()[0].0, shooter);
+}
+
+#[test]
+fn shooting_an_asteroid_scores_by_size() {
+    let (mut world, shooter, target) = duel_world();
+    world.remove_player(target);
+    world.asteroids[0].pos = cgmath::Point2 { x: 0.3, y: 0. };
+    world.asteroids[0].vel = cgmath::Vector2 { x: 0., y: 0. };
+
+    world.queue_input(PlayerInput { tick: 1, player: shooter, fire: true, ..Default::default() });
+    world.queue_input(PlayerInput { tick: 2, player: shooter, ..Default::default() });
+
+    let before = world.scoreboard().get(shooter).unwrap().score();
+
+    for _ in 0..30 {
+        world.step();
+    }
+
+    let score = world.scoreboard().get(shooter).unwrap();
+    assert_eq!(score.medium_asteroid_kills, 1);
+    assert_eq!(score.asteroid_kills(), 1);
+    assert!(score.score() > before);
+}
+
+#[test]
+fn smaller_asteroids_are_worth_more() {
+    let player = EntityId::from_parts(0, 0);
+    let mut points = Vec::new();
+
+    for size in [AsteroidSize::Small, AsteroidSize::Medium, AsteroidSize::Large] {
+        let mut scoreboard = Scoreboard::new();
+        scoreboard.record_asteroid_kill(player, size);
+        points.push(scoreboard.get(player).unwrap().score());
+    }
+
+    assert!(points[0] > points[1] && points[1] > points[2]);
+}
+
+#[test]
+fn players_out_of_lives_are_eliminated() {
+    let (mut world, _, target) = duel_world();
+    world.asteroids[0].pos = cgmath::Point2 { x: 0., y: -0.5 };
+    world.asteroids[0].vel = cgmath::Vector2 { x: 0., y: 0. };
+    world.step();
+
+    let mut deaths = 0;
+    while world.player(target).unwrap().state != PlayerState::Eliminated {
+        assert!(deaths < START_LIVES, "still playing after losing every life");
+
+        // Waits for the respawn and the invulnerability to be over
+        while !world.player(target).unwrap().is_alive() || world.player(target).unwrap().is_invulnerable(world.time()) {
+            world.step();
+        }
+
+        // Rams the asteroid fast enough to die in one hit, it overlaps it after a single tick
+        let asteroid = world.asteroids[0].pos;
+        let player = world.player_mut(target).unwrap();
+        player.pos = cgmath::Point2 { x: asteroid.x - 0.1, y: asteroid.y };
+        player.vel = cgmath::Vector2 { x: 5., y: 0. };
+
+        while world.player(target).unwrap().is_alive() {
+            world.step();
+        }
+        deaths += 1;
+    }
+
+    assert_eq!(deaths, START_LIVES);
+    assert_eq!(world.scoreboard().get(target).unwrap().lives, 0);
+}
@@ -0,0 +1,81 @@
+use game_logic::{World, WorldConfig, WorldSnapshot, SnapshotError, PlayerInput};
+
+/// A world that went through a bit of everything: streaming, shots, respawns and queued inputs
+fn played_world() -> World {
+    let mut world = World::new(3, 3, 7, WorldConfig::new());
+    let ids = world.player_ids();
+
+    world.queue_input(PlayerInput { tick: 1, player: ids[0], thrust: true, turn_left: true, fire: true, ..Default::default() });
+    world.queue_input(PlayerInput { tick: 1, player: ids[1], fire: true, turn_right: true, ..Default::default() });
+
+    for _ in 0..300 {
+        world.step();
+    }
+
+    // Still pending when the snapshot is taken
+    world.queue_input(PlayerInput { tick: world.tick() + 30, player: ids[0], brake: true, ..Default::default() });
+
+    return world;
+}
+
+/// Compares the binary form, it holds every bit of the state
+fn same_state(a: &WorldSnapshot, b: &WorldSnapshot) -> bool {
+    return a.to_bytes().unwrap() == b.to_bytes().unwrap();
+}
+
+fn run(world: &mut World, n_ticks: usize) {
+    for _ in 0..n_ticks {
+        world.step();
+    }
+}
+
+#[test]
+fn json_snapshot_restores_the_same_simulation() {
+    let mut original = played_world();
+    let json = original.snapshot().to_json().unwrap();
+    let mut restored = World::from_snapshot(WorldSnapshot::from_json(&json).unwrap());
+
+    assert!(same_state(&original.snapshot(), &restored.snapshot()));
+
+    run(&mut original, 300);
+    run(&mut restored, 300);
+
+    assert!(same_state(&original.snapshot(), &restored.snapshot()));
+}
+
+#[test]
+fn binary_snapshot_restores_the_same_simulation() {
+    let mut original = played_world();
+    let bytes = original.snapshot().to_bytes().unwrap();
+    let mut restored = World::from_snapshot(WorldSnapshot::from_bytes(&bytes).unwrap());
+
+    run(&mut original, 300);
+    run(&mut restored, 300);
+
+    assert!(same_state(&original.snapshot(), &restored.snapshot()));
+}
+
+#[test]
+fn snapshot_files_are_recognised() {
+    let world = played_world();
+    let dir = std::env::temp_dir();
+
+    for (name, format) in [("snapshot_test.json", game_logic::SnapshotFormat::Json), ("snapshot_test.bin", game_logic::SnapshotFormat::Binary)] {
+        let path = dir.join(name);
+        world.snapshot().save(&path, format).unwrap();
+
+        let loaded = WorldSnapshot::load(&path).unwrap();
+        assert!(same_state(&world.snapshot(), &loaded));
+
+        std::fs::remove_file(path).unwrap();
+    }
+}
+
+#[test]
+fn other_versions_are_refused() {
+    let mut snapshot = played_world().snapshot();
+    snapshot.version += 1;
+
+    assert!(matches!(WorldSnapshot::from_json(&snapshot.to_json().unwrap()), Err(SnapshotError::Version { .. })));
+    assert!(matches!(WorldSnapshot::from_bytes(&snapshot.to_bytes().unwrap()), Err(SnapshotError::Version { .. })));
+}
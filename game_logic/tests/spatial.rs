@@ -0,0 +1,70 @@
+use cgmath::Point2;
+
+use game_logic::spatial::SpatialIndex;
+
+fn point(x: f64, y: f64) -> Point2<f64> {
+    return Point2 { x, y };
+}
+
+#[test]
+fn queries_reach_into_the_neighbouring_cells() {
+    let mut index = SpatialIndex::new(1.);
+    // On both sides of the border between two cells, and on it
+    index.rebuild([point(0.95, 0.5), point(1.05, 0.5), point(1., 0.5), point(3., 0.5)]);
+
+    assert_eq!(index.query_radius(point(1., 0.5), 0.1), vec![0, 1, 2]);
+    assert_eq!(index.query_radius(point(0.9, 0.5), 0.1), vec![0, 2]);
+}
+
+#[test]
+fn the_radius_is_inclusive() {
+    let mut index = SpatialIndex::new(1.);
+    index.rebuild([point(0.5, 0.5)]);
+
+    assert_eq!(index.query_radius(point(0.5, 0.), 0.5), vec![0]);
+    assert!(index.query_radius(point(0.5, 0.), 0.49).is_empty());
+}
+
+#[test]
+fn negative_coordinates_have_their_own_cells() {
+    let mut index = SpatialIndex::new(1.);
+    index.rebuild([point(-0.5, -0.5), point(0.5, 0.5), point(-1.5, 0.5)]);
+
+    // Truncating toward zero would put the first one in the cell of the second
+    assert_eq!(index.cell((-1, -1)), &[0]);
+    assert_eq!(index.cell((0, 0)), &[1]);
+    assert_eq!(index.cell((-2, 0)), &[2]);
+
+    assert_eq!(index.query_radius(point(-0.6, -0.6), 0.2), vec![0]);
+    assert_eq!(index.query_radius(point(0., 0.), 0.8), vec![0, 1]);
+}
+
+#[test]
+fn moved_entities_are_found_where_they_are() {
+    let mut index = SpatialIndex::new(1.);
+    index.rebuild([point(0.5, 0.5), point(5.5, 5.5)]);
+
+    index.update([point(-3.5, 0.5), point(5.6, 5.5)]);
+
+    assert!(index.query_radius(point(0.5, 0.5), 0.5).is_empty());
+    assert_eq!(index.query_radius(point(-3.5, 0.5), 0.1), vec![0]);
+    assert_eq!(index.query_radius(point(5.5, 5.5), 0.2), vec![1]);
+    assert!(index.cell((0, 0)).is_empty());
+    assert_eq!(index.cell((-4, 0)), &[0]);
+}
+
+#[test]
+fn updates_add_the_new_entities_and_rebuild_after_removals() {
+    let mut index = SpatialIndex::new(1.);
+    index.rebuild([point(0.5, 0.5)]);
+
+    index.update([point(0.5, 0.5), point(2.5, 0.5)]);
+    assert_eq!(index.len(), 2);
+    assert_eq!(index.query_radius(point(2.5, 0.5), 0.1), vec![1]);
+
+    // The first one was removed, the second one took its index
+    index.update([point(2.5, 0.5)]);
+    assert_eq!(index.len(), 1);
+    assert_eq!(index.query_radius(point(2.5, 0.5), 0.1), vec![0]);
+    assert!(index.query_radius(point(0.5, 0.5), 0.1).is_empty());
+}
@@ -0,0 +1,99 @@
+use game_logic::{World, WorldConfig, WorldSnapshot, PlayerInput, DensityConfig};
+
+fn played_world() -> World {
+    let mut world = World::new(3, 3, 1234, WorldConfig::new());
+    let ids = world.player_ids();
+    world.queue_input(PlayerInput { tick: 1, player: ids[0], thrust: true, fire: true, ..Default::default() });
+
+    for _ in 0..120 {
+        world.step();
+    }
+
+    return world;
+}
+
+#[test]
+fn hash_ignores_the_order_of_the_entities() {
+    let world = played_world();
+
+    let mut snapshot = world.snapshot();
+    snapshot.asteroids.reverse();
+    snapshot.players.reverse();
+    snapshot.projectiles.reverse();
+    let shuffled = World::from_snapshot(snapshot);
+
+    assert_eq!(world.state_hash(), shuffled.state_hash());
+}
+
+#[test]
+fn hash_sees_the_smallest_change() {
+    let world = played_world();
+
+    let mut snapshot = world.snapshot();
+    let pos = &mut snapshot.asteroids[0].pos;
+    pos.x = f64::from_bits(pos.x.to_bits() + 1);
+    let moved = World::from_snapshot(snapshot);
+
+    assert_ne!(world.state_hash(), moved.state_hash());
+}
+
+#[test]
+fn worlds_in_sync_have_the_same_hash_every_tick() {
+    let mut world = played_world();
+    let mut copy = World::from_snapshot(WorldSnapshot::from_bytes(&world.snapshot().to_bytes().unwrap()).unwrap());
+
+    for _ in 0..60 {
+        world.step();
+        copy.step();
+        assert_eq!(world.state_hash(), copy.state_hash());
+    }
+}
+
+#[test]
+fn hash_sees_the_scoreboard_and_the_inputs() {
+    let world = played_world();
+    let id = world.player_ids()[0];
+    let hash = world.state_hash();
+
+    let mut snapshot = world.snapshot();
+    snapshot.scoreboard.get_mut(id).medium_asteroid_kills += 1;
+    assert_ne!(World::from_snapshot(snapshot).state_hash(), hash);
+
+    let mut snapshot = world.snapshot();
+    snapshot.pending_inputs.push(PlayerInput { tick: world.tick() + 10, player: id, fire: true, ..Default::default() });
+    assert_ne!(World::from_snapshot(snapshot).state_hash(), hash);
+
+    let mut snapshot = world.snapshot();
+    snapshot.active_inputs.iter_mut().find(|input| input.player == id).unwrap().brake ^= true;
+    assert_ne!(World::from_snapshot(snapshot).state_hash(), hash);
+}
+
+#[test]
+fn hash_sees_the_fire_cooldown() {
+    // Nothing but the players, so a shot leaves no other trace once its projectile expired
+    let config = WorldConfig { density: DensityConfig::Uniform { per_chunk: 0. }, ..WorldConfig::new() };
+    let mut shot = World::new(3, 3, 1234, config);
+    shot.asteroids.clear();
+    let other = shot.player_ids()[1];
+    shot.remove_player(other);
+    let mut idle = World::from_snapshot(shot.snapshot());
+
+    let id = shot.player_ids()[0];
+    shot.queue_input(PlayerInput { tick: 1, player: id, fire: true, ..Default::default() });
+    shot.queue_input(PlayerInput { tick: 2, player: id, ..Default::default() });
+    idle.queue_input(PlayerInput { tick: 2, player: id, ..Default::default() });
+
+    for _ in 0..30 {
+        shot.step();
+        idle.step();
+    }
+    assert!(!shot.projectiles.is_empty());
+
+    for _ in 0..120 {
+        shot.step();
+        idle.step();
+    }
+    assert!(shot.projectiles.is_empty());
+
+    assert_ne!(shot.state_hash(), idle.state_hash());
+}
@@ -0,0 +1,58 @@
+use std::time::Duration;
+
+use game_logic::{World, WorldConfig, ManualClock, WORLD_UPD_RATE};
+
+/// A world that catches up with all the time it is given, like a headless one
+fn world() -> World {
+    let mut world = World::new(3, 3, 8, WorldConfig::new());
+    world.set_clock(Box::new(ManualClock::new()));
+
+    return world;
+}
+
+#[test]
+fn a_step_is_one_tick_of_fixed_length() {
+    let mut world = world();
+
+    for _ in 0..60 {
+        world.step();
+    }
+
+    assert_eq!(world.tick(), 60);
+    assert_eq!(world.time(), WORLD_UPD_RATE * 60);
+}
+
+#[test]
+fn a_simulated_second_is_a_fixed_number_of_ticks() {
+    let mut world = world();
+
+    let n_ticks = world.advance(Duration::from_secs(1));
+
+    assert_eq!(n_ticks as u128, Duration::from_secs(1).as_nanos() / WORLD_UPD_RATE.as_nanos());
+    assert_eq!(world.tick(), n_ticks as u64);
+}
+
+#[test]
+fn the_remainder_is_kept_for_the_next_advance() {
+    let mut world = world();
+
+    assert_eq!(world.advance(WORLD_UPD_RATE / 2), 0);
+    assert!((world.tick_progress() - 0.5).abs() < 1e-9);
+
+    assert_eq!(world.advance(WORLD_UPD_RATE / 2), 1);
+    assert_eq!(world.tick(), 1);
+}
+
+#[test]
+fn uneven_frames_give_the_same_ticks_as_even_ones() {
+    let mut even = world();
+    let mut uneven = world();
+
+    for frame in 0..120u64 {
+        even.advance(Duration::from_millis(10));
+        uneven.advance(Duration::from_millis(if frame % 2 == 0 { 3 } else { 17 }));
+    }
+
+    assert_eq!(even.tick(), uneven.tick());
+    assert_eq!(even.state_hash(), uneven.state_hash());
+}
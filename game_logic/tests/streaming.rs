@@ -0,0 +1,129 @@
+use std::f64::consts::PI;
+
+use game_logic::{World, WorldConfig, DensityConfig};
+
+const SEED: u64 = 3;
+const PER_CHUNK: f64 = 3.;
+
+fn config() -> WorldConfig {
+    return WorldConfig { density: DensityConfig::Uniform { per_chunk: PER_CHUNK }, ..WorldConfig::new() };
+}
+
+/// A world with a single player, so the rings are easy to reason about
+fn lone_player_world(config: WorldConfig) -> World {
+    let mut world = World::new(3, 3, SEED, config);
+    let other = world.player_ids()[1];
+    world.remove_player(other);
+
+    return world;
+}
+
+/// Puts the player at `pos`, stopped so only the test moves it
+fn set_player(world: &mut World, pos: (f64, f64)) {
+    let id = world.player_ids()[0];
+    let player = world.player_mut(id).unwrap();
+
+    player.pos = cgmath::Point2 { x: pos.0, y: pos.1 };
+    player.vel = cgmath::Vector2 { x: 0., y: 0. };
+}
+
+/// The most chunks the unload ring can hold around a single player
+fn max_chunks(config: &WorldConfig) -> usize {
+    let radius = config.chunk_unload_dist + 1.;
+
+    return (PI * radius * radius).ceil() as usize;
+}
+
+#[test]
+fn flying_in_every_direction_stays_bounded() {
+    let config = config();
+    let max_chunks = max_chunks(&config);
+    // Fragments and drifting asteroids leave some margin over the generated ones
+    let max_asteroids = (max_chunks as f64 * PER_CHUNK * 2.) as usize;
+
+    for direction in 0..8 {
+        let angle = direction as f64 * PI / 4.;
+        let mut world = lone_player_world(config);
+
+        // Moved by hand, a ship hitting asteroids at this speed would die and stop
+        // Over a hundred chunks in a few hundred ticks, faster than any ship flies
+        for tick in 0..300 {
+            let dist = tick as f64 * 0.8;
+            set_player(&mut world, (angle.cos() * dist, angle.sin() * dist));
+            world.step();
+
+            assert!(world.asteroid_manager().chunks().len() <= max_chunks, "too many chunks loaded flying at {} rad", angle);
+            assert!(world.asteroids.len() <= max_asteroids, "too many asteroids flying at {} rad", angle);
+        }
+
+        // Every chunk behind the player was stored instead of kept loaded
+        assert!(world.asteroid_manager().chunk_store().len() > max_chunks);
+    }
+}
+
+#[test]
+fn loading_is_radial() {
+    let config = config();
+    let mut world = lone_player_world(config);
+    set_player(&mut world, (0., 0.));
+
+    for _ in 0..20 {
+        world.step();
+    }
+
+    // The player is on the corner of 4 chunks, so the centers are at half a chunk from whole coordinates
+    for ((x, y), _) in world.asteroid_manager().chunks() {
+        let dist = ((x as f64 + 0.5).powi(2) + (y as f64 + 0.5).powi(2)).sqrt();
+        assert!(dist <= config.chunk_load_dist, "chunk {:?} loaded {} chunks away", (x, y), dist);
+    }
+
+    let expected = (-10..10).flat_map(|x| (-10..10).map(move |y| (x, y)))
+        .filter(|(x, y)| ((*x as f64 + 0.5).powi(2) + (*y as f64 + 0.5).powi(2)).sqrt() <= config.chunk_load_dist)
+        .count();
+    assert_eq!(world.asteroid_manager().chunks().len(), expected);
+}
+
+#[test]
+fn loading_follows_the_budget() {
+    let config = WorldConfig { chunks_per_tick: 10, ..config() };
+    let mut world = lone_player_world(config);
+    set_player(&mut world, (0., 0.));
+
+    let mut loaded = 0;
+    for _ in 0..5 {
+        world.step();
+
+        let now_loaded = world.asteroid_manager().chunks().len();
+        assert!(now_loaded - loaded <= 10);
+        loaded = now_loaded;
+    }
+
+    // The closest chunks come first, the chunk of the player is always in the first batch
+    let mut world = lone_player_world(config);
+    set_player(&mut world, (0., 0.));
+    world.step();
+    assert!(world.asteroid_manager().chunks().iter().any(|(chunk, _)| *chunk == (0, 0)));
+}
+
+#[test]
+fn hysteresis_prevents_churn_on_the_edge() {
+    // Still asteroids, so none drifts out of the loaded chunks on its own
+    let config = WorldConfig { ast_speed_max: 1e-20, ..config() };
+    let mut world = lone_player_world(config);
+    set_player(&mut world, (0., 0.));
+
+    for _ in 0..20 {
+        world.step();
+    }
+
+    // Going back and forth by less than the gap between the rings never unloads anything
+    let step = (config.chunk_unload_dist - config.chunk_load_dist) * config.chunk_size * 0.9;
+
+    for i in 0..20 {
+        let x = if i % 2 == 0 { step } else { 0. };
+        set_player(&mut world, (x, 0.));
+        world.step();
+    }
+
+    assert!(world.asteroid_manager().chunk_store().is_empty());
+}
@@ -154,6 +154,18 @@ fn read_logs() -> Vec<LogEnveloppe> {
     return LOG.lock().unwrap().logs.clone();
 }
 
+/// Writes the new logs to the terminal instead of keeping them for the ui, for the programs without one
+pub fn print_new_logs() {
+    let new_logs: Vec<_> = LOG.lock().unwrap().new_logs.drain(..).collect();
+
+    for log in new_logs {
+        match log.level {
+            LogLevel::Log | LogLevel::Info => println!("{} {}", log.source, log.log),
+            LogLevel::Warn | LogLevel::Unexpected | LogLevel::Error => eprintln!("{} {:?}: {}", log.source, log.level, log.log),
+        }
+    }
+}
+
 pub struct UiLogger {
     info_enabled: bool,
     log_enabled: bool,
@@ -0,0 +1,144 @@
+use std::ops::Add;
+use std::time::{Duration, Instant};
+
+use serde::{Serialize, Deserialize};
+
+use game_logic::WORLD_UPD_RATE;
+
+/// A time on the protocol clock, in microseconds
+/// The protocol clock is the one of the server: tick `n` of the server's world starts at `n * WORLD_UPD_RATE`
+/// The clients sync their own clock on it, so the timestamps of every peer can be compared
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default, Serialize, Deserialize)]
+pub struct Timestamp {
+    micros: u64,
+}
+
+impl Timestamp {
+    pub const ZERO: Timestamp = Timestamp { micros: 0 };
+
+    pub fn from_micros(micros: u64) -> Timestamp {
+        return Timestamp { micros };
+    }
+
+    pub fn as_micros(&self) -> u64 {
+        return self.micros;
+    }
+
+    /// The start of a tick of the server
+    pub fn of_tick(tick: u64) -> Timestamp {
+        return Timestamp { micros: tick.saturating_mul(WORLD_UPD_RATE.as_micros() as u64) };
+    }
+
+    /// The tick of the server running at this time
+    pub fn tick(&self) -> u64 {
+        return self.micros / WORLD_UPD_RATE.as_micros() as u64;
+    }
+
+    /// Zero if `earlier` is in fact later
+    pub fn saturating_duration_since(&self, earlier: Timestamp) -> Duration {
+        return Duration::from_micros(self.micros.saturating_sub(earlier.micros));
+    }
+}
+
+impl Add<Duration> for Timestamp {
+    type Output = Timestamp;
+
+    fn add(self, rhs: Duration) -> Timestamp {
+        return Timestamp { micros: self.micros.saturating_add(rhs.as_micros() as u64) };
+    }
+}
+
+/// The protocol clock as the server reads it, driven by the ticks of its world
+/// Between two ticks it follows the system clock, so a late tick doesn't make it drift from the tick numbers
+#[derive(Debug, Clone, Copy)]
+pub struct ServerClock {
+    tick_time: Timestamp,
+    /// When the last tick was simulated
+    at: Instant,
+}
+
+impl ServerClock {
+    pub fn new(tick: u64, now: Instant) -> ServerClock {
+        return ServerClock { tick_time: Timestamp::of_tick(tick), at: now };
+    }
+
+    /// To call after every tick
+    pub fn ticked(&mut self, tick: u64, now: Instant) {
+        self.tick_time = Timestamp::of_tick(tick);
+        self.at = now;
+    }
+
+    pub fn now(&self, now: Instant) -> Timestamp {
+        return self.tick_time + now.saturating_duration_since(self.at);
+    }
+}
+
+/// The protocol clock as a client reads it, estimated from the time sync exchanges with the server
+/// The client sends the time of its own clock, the server answers with it and its time,
+/// like NTP the server's time is taken as read halfway through the round trip
+#[derive(Debug, Clone, Copy)]
+pub struct ClockSync {
+    /// The origin of the local clock
+    origin: Instant,
+    /// The server time minus the local time, none before the first exchange
+    /// Wide enough for any pair of timestamps, the server's come from the network
+    offset: Option<i128>,
+    /// The shortest round trip seen, the exchanges taking much longer were delayed on the way and are ignored
+    min_rtt: Option<Duration>,
+}
+
+impl ClockSync {
+    pub fn new(now: Instant) -> ClockSync {
+        return ClockSync { origin: now, offset: None, min_rtt: None };
+    }
+
+    /// The time of the local clock, the one sent to the server in the exchanges
+    pub fn local_time(&self, now: Instant) -> Timestamp {
+        return Timestamp::from_micros(now.saturating_duration_since(self.origin).as_micros() as u64);
+    }
+
+    /// The instant at which the local clock read `local`, none if it can't be represented
+    pub fn instant_of(&self, local: Timestamp) -> Option<Instant> {
+        return self.origin.checked_add(Duration::from_micros(local.as_micros()));
+    }
+
+    pub fn is_synced(&self) -> bool {
+        return self.offset.is_some();
+    }
+
+    /// Takes in the answer of the server, `sent` is the local time the client sent, `server_time` the time of the server's answer
+    /// An answer to a time the local clock hasn't reached yet wasn't sent by this client, it is ignored
+    pub fn sample(&mut self, sent: Timestamp, server_time: Timestamp, now: Instant) {
+        let local_now = self.local_time(now);
+        if sent > local_now {
+            return;
+        }
+
+        let rtt = local_now.saturating_duration_since(sent);
+        let offset = (server_time + rtt / 2).as_micros() as i128 - local_now.as_micros() as i128;
+
+        match (self.offset, self.min_rtt) {
+            (Some(_), Some(min_rtt)) if rtt >= min_rtt => {
+                if rtt <= min_rtt * 2 {
+                    self.offset = self.offset.map(|current| current + (offset - current) / 8);
+                } else {
+                    // If the route got slower for good, its exchanges are taken again after a few ones
+                    self.min_rtt = Some(min_rtt + min_rtt / 8 + Duration::from_micros(100));
+                }
+            },
+            // A shorter round trip gives a better estimate, it is taken as it is
+            _ => {
+                self.offset = Some(offset);
+                self.min_rtt = Some(rtt);
+            },
+        }
+    }
+
+    /// The protocol time now, none before the first exchange
+    pub fn server_time(&self, now: Instant) -> Option<Timestamp> {
+        let offset = self.offset?;
+        let micros = self.local_time(now).as_micros() as i128 + offset;
+
+        return Some(Timestamp::from_micros(micros.clamp(0, u64::MAX as i128) as u64));
+    }
+}
@@ -0,0 +1,721 @@
+use std::fmt;
+use std::f32::consts::TAU;
+use std::time::Duration;
+
+use cgmath::{Point2, Vector2};
+
+use game_logic::{Player, PlayerState, PlayerInput, EntityId, WorldConfig, DensityConfig, Asteroid, AsteroidSize, Projectile};
+
+use super::{UpMsgBox, DownMsgBox, GameUpdate, PlayerLook, WorldHash, Timestamp, CHUNK_PART_ASTEROIDS};
+
+/// The first byte of every datagram, bumped whenever the layout of a message changes
+/// A peer speaking another version is rejected instead of being misread
+pub const PROTOCOL_VERSION: u8 = 6;
+
+/// Positions are sent in fixed point, a 4096th of a unit, up to 524288 units from the origin
+const POS_SCALE: f64 = 4096.;
+/// Velocities are sent in fixed point, a 65536th of a unit per second, up to 32768 units per second
+const VEL_SCALE: f64 = 65536.;
+/// The flame animation moves by quarters of a frame
+const FLAME_SCALE: f32 = 4.;
+/// Health is sent in hundredths
+const HEALTH_SCALE: f32 = 100.;
+
+// The biggest datagram of each message, with the worst case of every field, ids of `EntityId::NONE` and a dead player
+// Every datagram starts with the protocol version and the tag of the message, the game updates add their own tag
+// A player takes at most 40 bytes: its id (10), position (8), velocity (8), rotation (2), flame (1), health (2),
+// state (5) and invulnerability (4), its look takes 21 bytes: 5 colours of 4 channels and its image
+// An asteroid takes 34 bytes: its id (10), position (8), velocity (8), rotation (2), spin (4), size (1) and image (1),
+// a projectile 40 bytes: its id (10), position (8), velocity (8), owner (10) and time to live (4)
+// A timestamp takes at most 10 bytes, 5 during the first 9 hours of a server
+
+/// The version, the tag and the look
+pub const UP_NEW_CONNECTION_SIZE: usize = 2 + 21;
+/// The version, the tag and the time
+pub const UP_KEEP_ALIVE_SIZE: usize = 2 + 10;
+/// The version, the tag, the player and the time
+pub const UP_PLAYER_UPDATE_SIZE: usize = 2 + 40 + 10;
+/// The version, the tag, the tick (10), the player (10) and the actions packed in a byte
+pub const UP_INPUT_SIZE: usize = 2 + 10 + 10 + 1;
+pub const UP_DISCONNECT_SIZE: usize = 2;
+/// The version, the tag, the tick and the hash
+pub const UP_WORLD_HASH_SIZE: usize = 2 + 10 + 8;
+/// The version, the tag, the key, the id, the seed, the config (98) and the time
+pub const DOWN_CONNECTION_ACKNOWLEDGED_SIZE: usize = 2 + 8 + 10 + 8 + 98 + 10;
+pub const DOWN_SERVER_CLOSING_SIZE: usize = 2;
+/// The version, the tag, the time of the client and the time of the server
+pub const DOWN_KEEP_ALIVE_SIZE: usize = 2 + 10 + 10;
+pub const DOWN_UNRECOGNISED_SIZE: usize = 2;
+/// Sent for every player at every tick, the colours are left out
+pub const DOWN_PLAYER_UPDATE_SIZE: usize = 3 + 10 + 40 + 10;
+/// The only update with the colours of the player
+pub const DOWN_NEW_PLAYER_SIZE: usize = 3 + 10 + 40 + 21;
+pub const DOWN_PLAYER_DISCONNECT_SIZE: usize = 3 + 10;
+/// The chunk (20), the time, the part and the count of parts, the count of asteroids and the asteroids
+pub const DOWN_CHUNK_STATE_SIZE: usize = 3 + 20 + 10 + 2 + 1 + CHUNK_PART_ASTEROIDS * 34;
+pub const DOWN_NEW_PROJECTILE_SIZE: usize = 3 + 40;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CodecError {
+    /// The peer speaks another version of the protocol
+    Version {
+        got: u8,
+    },
+    UnknownTag {
+        what: &'static str,
+        tag: u8,
+    },
+    /// The datagram ends in the middle of a message
+    Truncated,
+    /// The message ends before the datagram
+    TrailingBytes(usize),
+    /// A variable length integer doesn't fit its type
+    Overflow,
+    /// The world config was decoded but the simulation can't run with it
+    InvalidConfig,
+}
+
+impl fmt::Display for CodecError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        return match self {
+            CodecError::Version { got } => write!(f, "Protocol version {} instead of {}", got, PROTOCOL_VERSION),
+            CodecError::UnknownTag { what, tag } => write!(f, "Unknown {} tag {}", what, tag),
+            CodecError::Truncated => write!(f, "Truncated message"),
+            CodecError::TrailingBytes(n) => write!(f, "{} bytes after the end of the message", n),
+            CodecError::Overflow => write!(f, "Integer too big for its type"),
+            CodecError::InvalidConfig => write!(f, "World config out of its ranges"),
+        };
+    }
+}
+
+impl std::error::Error for CodecError {}
+
+impl UpMsgBox {
+    /// The datagram holding the message
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut w = Writer::new();
+
+        match self {
+            UpMsgBox::NewConnection { look } => {
+                w.u8(0);
+                look.encode(&mut w);
+            },
+            UpMsgBox::KeepAlive { time } => {
+                w.u8(1);
+                time.encode(&mut w);
+            },
+            UpMsgBox::PlayerUpdate { player, time } => {
+                w.u8(2);
+                player.encode(&mut w);
+                time.encode(&mut w);
+            },
+            UpMsgBox::Input { input } => {
+                w.u8(3);
+                input.encode(&mut w);
+            },
+            UpMsgBox::Disconect => w.u8(4),
+            UpMsgBox::WorldHash(hash) => {
+                w.u8(5);
+                hash.encode(&mut w);
+            },
+        }
+
+        return w.bytes;
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<UpMsgBox, CodecError> {
+        let mut r = Reader::new(bytes)?;
+
+        let msg = match r.u8()? {
+            0 => UpMsgBox::NewConnection { look: PlayerLook::decode(&mut r)? },
+            1 => UpMsgBox::KeepAlive { time: Timestamp::decode(&mut r)? },
+            2 => UpMsgBox::PlayerUpdate { player: Player::decode(&mut r)?, time: Timestamp::decode(&mut r)? },
+            3 => UpMsgBox::Input { input: PlayerInput::decode(&mut r)? },
+            4 => UpMsgBox::Disconect,
+            5 => UpMsgBox::WorldHash(WorldHash::decode(&mut r)?),
+            tag => return Err(CodecError::UnknownTag { what: "UpMsgBox", tag }),
+        };
+        r.finish()?;
+
+        return Ok(msg);
+    }
+
+    /// The most bytes this kind of message can take, see the `_SIZE` constants
+    pub fn size_budget(&self) -> usize {
+        return match self {
+            UpMsgBox::NewConnection { .. } => UP_NEW_CONNECTION_SIZE,
+            UpMsgBox::KeepAlive { .. } => UP_KEEP_ALIVE_SIZE,
+            UpMsgBox::PlayerUpdate { .. } => UP_PLAYER_UPDATE_SIZE,
+            UpMsgBox::Input { .. } => UP_INPUT_SIZE,
+            UpMsgBox::Disconect => UP_DISCONNECT_SIZE,
+            UpMsgBox::WorldHash(_) => UP_WORLD_HASH_SIZE,
+        };
+    }
+}
+
+impl DownMsgBox {
+    /// The datagram holding the message
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut w = Writer::new();
+
+        match self {
+            DownMsgBox::ConnectionAcknowleged { key, your_id, world_seed, world_config, server_time } => {
+                w.u8(0);
+                w.u64(*key);
+                your_id.encode(&mut w);
+                w.u64(*world_seed);
+                world_config.encode(&mut w);
+                server_time.encode(&mut w);
+            },
+            DownMsgBox::ServerClosing => w.u8(1),
+            DownMsgBox::KeepAlive { time, server_time } => {
+                w.u8(2);
+                time.encode(&mut w);
+                server_time.encode(&mut w);
+            },
+            DownMsgBox::GameUpdate(update) => {
+                w.u8(3);
+                update.encode(&mut w);
+            },
+            DownMsgBox::Unrecognised => w.u8(4),
+        }
+
+        return w.bytes;
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<DownMsgBox, CodecError> {
+        let mut r = Reader::new(bytes)?;
+
+        let msg = match r.u8()? {
+            0 => DownMsgBox::ConnectionAcknowleged {
+                key: r.u64()?,
+                your_id: EntityId::decode(&mut r)?,
+                world_seed: r.u64()?,
+                world_config: WorldConfig::decode(&mut r)?,
+                server_time: Timestamp::decode(&mut r)?,
+            },
+            1 => DownMsgBox::ServerClosing,
+            2 => DownMsgBox::KeepAlive { time: Timestamp::decode(&mut r)?, server_time: Timestamp::decode(&mut r)? },
+            3 => DownMsgBox::GameUpdate(GameUpdate::decode(&mut r)?),
+            4 => DownMsgBox::Unrecognised,
+            tag => return Err(CodecError::UnknownTag { what: "DownMsgBox", tag }),
+        };
+        r.finish()?;
+
+        return Ok(msg);
+    }
+
+    /// The most bytes this kind of message can take, see the `_SIZE` constants
+    pub fn size_budget(&self) -> usize {
+        return match self {
+            DownMsgBox::ConnectionAcknowleged { .. } => DOWN_CONNECTION_ACKNOWLEDGED_SIZE,
+            DownMsgBox::ServerClosing => DOWN_SERVER_CLOSING_SIZE,
+            DownMsgBox::KeepAlive { .. } => DOWN_KEEP_ALIVE_SIZE,
+            DownMsgBox::GameUpdate(GameUpdate::PlayerUpdate { .. }) => DOWN_PLAYER_UPDATE_SIZE,
+            DownMsgBox::GameUpdate(GameUpdate::NewPlayer { .. }) => DOWN_NEW_PLAYER_SIZE,
+            DownMsgBox::GameUpdate(GameUpdate::PlayerDisconnect { .. }) => DOWN_PLAYER_DISCONNECT_SIZE,
+            DownMsgBox::GameUpdate(GameUpdate::ChunkState { .. }) => DOWN_CHUNK_STATE_SIZE,
+            DownMsgBox::GameUpdate(GameUpdate::NewProjectile { .. }) => DOWN_NEW_PROJECTILE_SIZE,
+            DownMsgBox::Unrecognised => DOWN_UNRECOGNISED_SIZE,
+        };
+    }
+}
+
+struct Writer {
+    bytes: Vec<u8>,
+}
+
+impl Writer {
+    fn new() -> Writer {
+        return Writer { bytes: vec![PROTOCOL_VERSION] };
+    }
+
+    fn u8(&mut self, value: u8) {
+        self.bytes.push(value);
+    }
+
+    fn u16(&mut self, value: u16) {
+        self.bytes.extend_from_slice(&value.to_le_bytes());
+    }
+
+    fn u32(&mut self, value: u32) {
+        self.bytes.extend_from_slice(&value.to_le_bytes());
+    }
+
+    fn i32(&mut self, value: i32) {
+        self.bytes.extend_from_slice(&value.to_le_bytes());
+    }
+
+    fn u64(&mut self, value: u64) {
+        self.bytes.extend_from_slice(&value.to_le_bytes());
+    }
+
+    fn f32(&mut self, value: f32) {
+        self.bytes.extend_from_slice(&value.to_le_bytes());
+    }
+
+    fn f64(&mut self, value: f64) {
+        self.bytes.extend_from_slice(&value.to_le_bytes());
+    }
+
+    /// 7 bits per byte, the high bit tells if more follow, so small values take a single byte
+    fn varint(&mut self, mut value: u64) {
+        while value >= 0x80 {
+            self.bytes.push(value as u8 | 0x80);
+            value >>= 7;
+        }
+        self.bytes.push(value as u8);
+    }
+
+    /// Zigzag encoded, so small negative values take a single byte too
+    fn signed_varint(&mut self, value: i64) {
+        self.varint(((value << 1) ^ (value >> 63)) as u64);
+    }
+
+    /// In millis, saturated at about 49 days
+    fn millis(&mut self, value: Duration) {
+        self.u32(value.as_millis().min(u32::MAX as u128) as u32);
+    }
+}
+
+struct Reader<'a> {
+    bytes: &'a [u8],
+}
+
+impl<'a> Reader<'a> {
+    /// Checks the version of the datagram
+    fn new(bytes: &'a [u8]) -> Result<Reader<'a>, CodecError> {
+        let mut r = Reader { bytes };
+
+        let version = r.u8()?;
+        if version != PROTOCOL_VERSION {
+            return Err(CodecError::Version { got: version });
+        }
+
+        return Ok(r);
+    }
+
+    fn take<const N: usize>(&mut self) -> Result<[u8; N], CodecError> {
+        if self.bytes.len() < N {
+            return Err(CodecError::Truncated);
+        }
+
+        let (head, rest) = self.bytes.split_at(N);
+        self.bytes = rest;
+
+        return Ok(head.try_into().unwrap());
+    }
+
+    fn u8(&mut self) -> Result<u8, CodecError> {
+        return Ok(self.take::<1>()?[0]);
+    }
+
+    fn u16(&mut self) -> Result<u16, CodecError> {
+        return Ok(u16::from_le_bytes(self.take()?));
+    }
+
+    fn u32(&mut self) -> Result<u32, CodecError> {
+        return Ok(u32::from_le_bytes(self.take()?));
+    }
+
+    fn i32(&mut self) -> Result<i32, CodecError> {
+        return Ok(i32::from_le_bytes(self.take()?));
+    }
+
+    fn u64(&mut self) -> Result<u64, CodecError> {
+        return Ok(u64::from_le_bytes(self.take()?));
+    }
+
+    fn f32(&mut self) -> Result<f32, CodecError> {
+        return Ok(f32::from_le_bytes(self.take()?));
+    }
+
+    fn f64(&mut self) -> Result<f64, CodecError> {
+        return Ok(f64::from_le_bytes(self.take()?));
+    }
+
+    fn varint(&mut self) -> Result<u64, CodecError> {
+        let mut value = 0;
+
+        for shift in (0..64).step_by(7) {
+            let byte = self.u8()?;
+            // The 10th byte only has room for the last bit
+            if shift == 63 && byte > 1 {
+                return Err(CodecError::Overflow);
+            }
+
+            value |= ((byte & 0x7f) as u64) << shift;
+            if byte & 0x80 == 0 {
+                return Ok(value);
+            }
+        }
+
+        return Err(CodecError::Overflow);
+    }
+
+    fn varint_u32(&mut self) -> Result<u32, CodecError> {
+        return self.varint()?.try_into().map_err(|_| CodecError::Overflow);
+    }
+
+    fn signed_varint(&mut self) -> Result<i64, CodecError> {
+        let value = self.varint()?;
+        return Ok((value >> 1) as i64 ^ -((value & 1) as i64));
+    }
+
+    fn millis(&mut self) -> Result<Duration, CodecError> {
+        return Ok(Duration::from_millis(self.u32()? as u64));
+    }
+
+    /// A datagram holds a single message
+    fn finish(&self) -> Result<(), CodecError> {
+        if !self.bytes.is_empty() {
+            return Err(CodecError::TrailingBytes(self.bytes.len()));
+        }
+
+        return Ok(());
+    }
+}
+
+/// The player as its receiver decodes it, with the values rounded like the codec sends them
+pub(crate) fn as_sent(player: &Player) -> Player {
+    let mut w = Writer { bytes: Vec::new() };
+    player.encode(&mut w);
+
+    return Player::decode(&mut Reader { bytes: &w.bytes }).expect("A player decodes from its own encoding");
+}
+
+trait Encode {
+    fn encode(&self, w: &mut Writer);
+}
+
+trait Decode: Sized {
+    fn decode(r: &mut Reader) -> Result<Self, CodecError>;
+}
+
+impl Encode for GameUpdate {
+    fn encode(&self, w: &mut Writer) {
+        match self {
+            GameUpdate::PlayerUpdate { id, player, time } => {
+                w.u8(0);
+                id.encode(w);
+                player.encode(w);
+                time.encode(w);
+            },
+            GameUpdate::NewPlayer { id, player } => {
+                w.u8(1);
+                id.encode(w);
+                player.encode(w);
+                PlayerLook::of(player).encode(w);
+            },
+            GameUpdate::PlayerDisconnect { id } => {
+                w.u8(2);
+                id.encode(w);
+            },
+            GameUpdate::ChunkState { chunk, time, part, n_parts, asteroids } => {
+                w.u8(3);
+                w.signed_varint(chunk.0);
+                w.signed_varint(chunk.1);
+                time.encode(w);
+                w.u8(*part);
+                w.u8(*n_parts);
+                w.u8(asteroids.len() as u8);
+                for asteroid in asteroids {
+                    asteroid.encode(w);
+                }
+            },
+            GameUpdate::NewProjectile { projectile } => {
+                w.u8(4);
+                projectile.encode(w);
+            },
+        }
+    }
+}
+
+impl Decode for GameUpdate {
+    fn decode(r: &mut Reader) -> Result<Self, CodecError> {
+        return Ok(match r.u8()? {
+            0 => GameUpdate::PlayerUpdate { id: EntityId::decode(r)?, player: Player::decode(r)?, time: Timestamp::decode(r)? },
+            1 => {
+                let id = EntityId::decode(r)?;
+                let mut player = Player::decode(r)?;
+                PlayerLook::decode(r)?.apply(&mut player);
+
+                GameUpdate::NewPlayer { id, player }
+            },
+            2 => GameUpdate::PlayerDisconnect { id: EntityId::decode(r)? },
+            3 => {
+                let chunk = (r.signed_varint()?, r.signed_varint()?);
+                let time = Timestamp::decode(r)?;
+                let part = r.u8()?;
+                let n_parts = r.u8()?;
+                let n_asteroids = r.u8()?;
+                let asteroids = (0..n_asteroids).map(|_| Asteroid::decode(r)).collect::<Result<_, _>>()?;
+
+                GameUpdate::ChunkState { chunk, time, part, n_parts, asteroids }
+            },
+            4 => GameUpdate::NewProjectile { projectile: Projectile::decode(r)? },
+            tag => return Err(CodecError::UnknownTag { what: "GameUpdate", tag }),
+        });
+    }
+}
+
+impl Encode for EntityId {
+    fn encode(&self, w: &mut Writer) {
+        w.varint(self.index() as u64);
+        w.varint(self.generation() as u64);
+    }
+}
+
+impl Decode for EntityId {
+    fn decode(r: &mut Reader) -> Result<Self, CodecError> {
+        return Ok(EntityId::from_parts(r.varint_u32()?, r.varint_u32()?));
+    }
+}
+
+/// Only what changes during the game, the look is sent on its own
+/// The fields private to the simulation aren't sent, the decoded player has the ones of a new player
+impl Encode for Player {
+    fn encode(&self, w: &mut Writer) {
+        self.id.encode(w);
+        // Saturated far away, where no ship goes
+        w.i32((self.pos.x * POS_SCALE).round() as i32);
+        w.i32((self.pos.y * POS_SCALE).round() as i32);
+        w.i32((self.vel.x * VEL_SCALE).round() as i32);
+        w.i32((self.vel.y * VEL_SCALE).round() as i32);
+        // The whole turn is spread over the 16 bits, the angle comes back between 0 and 2 pi
+        w.u16(((self.rot.rem_euclid(TAU) / TAU * 65536.).round() as u32 % 65536) as u16);
+        w.u8((self.flame_frame * FLAME_SCALE).round() as i8 as u8);
+        w.u16((self.health * HEALTH_SCALE).round() as u16);
+        match self.state {
+            PlayerState::Alive => w.u8(0),
+            PlayerState::Dead { respawn_time } => {
+                w.u8(1);
+                w.millis(respawn_time);
+            },
+            PlayerState::Eliminated => w.u8(2),
+        }
+        w.millis(self.invulnerable_until);
+    }
+}
+
+impl Decode for Player {
+    fn decode(r: &mut Reader) -> Result<Self, CodecError> {
+        let mut player = Player::new();
+
+        player.id = EntityId::decode(r)?;
+        player.pos.x = r.i32()? as f64 / POS_SCALE;
+        player.pos.y = r.i32()? as f64 / POS_SCALE;
+        player.vel.x = r.i32()? as f64 / VEL_SCALE;
+        player.vel.y = r.i32()? as f64 / VEL_SCALE;
+        player.rot = r.u16()? as f32 / 65536. * TAU;
+        player.flame_frame = r.u8()? as i8 as f32 / FLAME_SCALE;
+        player.health = r.u16()? as f32 / HEALTH_SCALE;
+        player.state = match r.u8()? {
+            0 => PlayerState::Alive,
+            1 => PlayerState::Dead { respawn_time: r.millis()? },
+            2 => PlayerState::Eliminated,
+            tag => return Err(CodecError::UnknownTag { what: "PlayerState", tag }),
+        };
+        player.invulnerable_until = r.millis()?;
+
+        return Ok(player);
+    }
+}
+
+/// The image is sent modulo 256, the time of spawn isn't sent
+impl Encode for Asteroid {
+    fn encode(&self, w: &mut Writer) {
+        self.id.encode(w);
+        w.i32((self.pos.x * POS_SCALE).round() as i32);
+        w.i32((self.pos.y * POS_SCALE).round() as i32);
+        w.i32((self.vel.x * VEL_SCALE).round() as i32);
+        w.i32((self.vel.y * VEL_SCALE).round() as i32);
+        w.u16(((self.rot.rem_euclid(TAU) / TAU * 65536.).round() as u32 % 65536) as u16);
+        w.f32(self.rot_speed);
+        w.u8(match self.size {
+            AsteroidSize::Small => 0,
+            AsteroidSize::Medium => 1,
+            AsteroidSize::Large => 2,
+        });
+        w.u8(self.img_idx as u8);
+    }
+}
+
+impl Decode for Asteroid {
+    fn decode(r: &mut Reader) -> Result<Self, CodecError> {
+        return Ok(Asteroid {
+            id: EntityId::decode(r)?,
+            pos: Point2 { x: r.i32()? as f64 / POS_SCALE, y: r.i32()? as f64 / POS_SCALE },
+            vel: Vector2 { x: r.i32()? as f64 / VEL_SCALE, y: r.i32()? as f64 / VEL_SCALE },
+            rot: r.u16()? as f32 / 65536. * TAU,
+            rot_speed: r.f32()?,
+            size: match r.u8()? {
+                0 => AsteroidSize::Small,
+                1 => AsteroidSize::Medium,
+                2 => AsteroidSize::Large,
+                tag => return Err(CodecError::UnknownTag { what: "AsteroidSize", tag }),
+            },
+            img_idx: r.u8()? as i32,
+            spawn_time: 0.,
+        });
+    }
+}
+
+impl Encode for Projectile {
+    fn encode(&self, w: &mut Writer) {
+        self.id.encode(w);
+        w.i32((self.pos.x * POS_SCALE).round() as i32);
+        w.i32((self.pos.y * POS_SCALE).round() as i32);
+        w.i32((self.vel.x * VEL_SCALE).round() as i32);
+        w.i32((self.vel.y * VEL_SCALE).round() as i32);
+        self.owner.encode(w);
+        w.millis(self.expire_time);
+    }
+}
+
+impl Decode for Projectile {
+    fn decode(r: &mut Reader) -> Result<Self, CodecError> {
+        return Ok(Projectile {
+            id: EntityId::decode(r)?,
+            pos: Point2 { x: r.i32()? as f64 / POS_SCALE, y: r.i32()? as f64 / POS_SCALE },
+            vel: Vector2 { x: r.i32()? as f64 / VEL_SCALE, y: r.i32()? as f64 / VEL_SCALE },
+            owner: EntityId::decode(r)?,
+            expire_time: r.millis()?,
+        });
+    }
+}
+
+/// The colours take a byte per channel
+impl Encode for PlayerLook {
+    fn encode(&self, w: &mut Writer) {
+        for color in [self.accent_color_0, self.accent_color_1, self.accent_color_2, self.accent_color_3, self.accent_flame_color] {
+            for channel in color {
+                w.u8((channel.clamp(0., 1.) * 255.).round() as u8);
+            }
+        }
+        w.u8(self.player_img.clamp(0, u8::MAX as i32) as u8);
+    }
+}
+
+impl Decode for PlayerLook {
+    fn decode(r: &mut Reader) -> Result<Self, CodecError> {
+        let mut colors = [[0.; 4]; 5];
+        for color in &mut colors {
+            for channel in color {
+                *channel = r.u8()? as f32 / 255.;
+            }
+        }
+
+        return Ok(PlayerLook {
+            accent_color_0: colors[0],
+            accent_color_1: colors[1],
+            accent_color_2: colors[2],
+            accent_color_3: colors[3],
+            accent_flame_color: colors[4],
+            player_img: r.u8()? as i32,
+        });
+    }
+}
+
+/// The actions are packed in a byte, a bit each
+impl Encode for PlayerInput {
+    fn encode(&self, w: &mut Writer) {
+        w.varint(self.tick);
+        self.player.encode(w);
+
+        let flags = [self.thrust, self.turn_left, self.turn_right, self.fire, self.brake];
+        w.u8(flags.iter().enumerate().fold(0, |byte, (bit, flag)| byte | ((*flag as u8) << bit)));
+    }
+}
+
+impl Decode for PlayerInput {
+    fn decode(r: &mut Reader) -> Result<Self, CodecError> {
+        let tick = r.varint()?;
+        let player = EntityId::decode(r)?;
+        let flags = r.u8()?;
+        let bit = |bit: u8| flags & (1 << bit) != 0;
+
+        return Ok(PlayerInput { tick, player, thrust: bit(0), turn_left: bit(1), turn_right: bit(2), fire: bit(3), brake: bit(4) });
+    }
+}
+
+impl Encode for WorldHash {
+    fn encode(&self, w: &mut Writer) {
+        w.varint(self.tick);
+        w.u64(self.hash);
+    }
+}
+
+impl Decode for WorldHash {
+    fn decode(r: &mut Reader) -> Result<Self, CodecError> {
+        return Ok(WorldHash { tick: r.varint()?, hash: r.u64()? });
+    }
+}
+
+/// Sent whole, the client must simulate with exactly the same values
+impl Encode for WorldConfig {
+    fn encode(&self, w: &mut Writer) {
+        w.f64(self.ast_speed_max);
+        w.f32(self.ast_rot_speed_max);
+        match self.density {
+            DensityConfig::Uniform { per_chunk } => {
+                w.u8(0);
+                w.f64(per_chunk);
+            },
+            DensityConfig::Noise { max_per_chunk, scale, void_level, octaves } => {
+                w.u8(1);
+                w.f64(max_per_chunk);
+                w.f64(scale);
+                w.f64(void_level);
+                w.varint(octaves as u64);
+            },
+        }
+        w.f64(self.ast_density);
+        w.f64(self.chunk_size);
+        w.f64(self.chunk_load_dist);
+        w.f64(self.chunk_unload_dist);
+        w.varint(self.chunks_per_tick as u64);
+        w.varint(self.chunk_store_capacity as u64);
+        w.f32(self.player_rot_speed);
+    }
+}
+
+impl Decode for WorldConfig {
+    fn decode(r: &mut Reader) -> Result<Self, CodecError> {
+        let ast_speed_max = r.f64()?;
+        let ast_rot_speed_max = r.f32()?;
+        let density = match r.u8()? {
+            0 => DensityConfig::Uniform { per_chunk: r.f64()? },
+            1 => DensityConfig::Noise { max_per_chunk: r.f64()?, scale: r.f64()?, void_level: r.f64()?, octaves: r.varint_u32()? },
+            tag => return Err(CodecError::UnknownTag { what: "DensityConfig", tag }),
+        };
+
+        let config = WorldConfig {
+            ast_speed_max,
+            ast_rot_speed_max,
+            density,
+            ast_density: r.f64()?,
+            chunk_size: r.f64()?,
+            chunk_load_dist: r.f64()?,
+            chunk_unload_dist: r.f64()?,
+            chunks_per_tick: r.varint()?.try_into().map_err(|_| CodecError::Overflow)?,
+            chunk_store_capacity: r.varint()?.try_into().map_err(|_| CodecError::Overflow)?,
+            player_rot_speed: r.f32()?,
+        };
+        config.validate().map_err(|_| CodecError::InvalidConfig)?;
+
+        return Ok(config);
+    }
+}
+
+impl Encode for Timestamp {
+    fn encode(&self, w: &mut Writer) {
+        w.varint(self.as_micros());
+    }
+}
+
+impl Decode for Timestamp {
+    fn decode(r: &mut Reader) -> Result<Self, CodecError> {
+        return Ok(Timestamp::from_micros(r.varint()?));
+    }
+}
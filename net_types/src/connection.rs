@@ -0,0 +1,135 @@
+use std::time::{Duration, Instant};
+
+use super::TIMEOUT;
+
+/// How often a keep alive is sent, a few of them can be lost before the peer times out
+pub const KEEP_ALIVE_RATE: Duration = Duration::from_millis(250);
+
+/// How long a peer has to answer a connection request
+pub const CONNECT_TIMEOUT: Duration = Duration::from_secs(5);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConnectionState {
+    /// Waiting for the peer to acknowledge the connection
+    Connecting,
+    Connected,
+    Disconnected(DisconnectReason),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DisconnectReason {
+    /// Nothing was heard from the peer for longer than `TIMEOUT`, or `CONNECT_TIMEOUT` while connecting
+    TimedOut,
+    /// One of the sides ended the connection
+    Closed,
+    /// The peer doesn't know this side, like after it dropped it
+    Refused,
+}
+
+/// The link with a peer, used the same way by the client and the server
+/// Every message received counts as a sign of life, the keep alives are only needed when nothing else is sent
+/// The client sends the keep alives and the server echoes them, the client measures the round trip time from the echo
+#[derive(Debug, Clone, Copy)]
+pub struct Connection {
+    state: ConnectionState,
+    /// When the connection started, then when it was acknowledged
+    since: Instant,
+    last_heard: Instant,
+    last_keep_alive: Instant,
+    /// Smoothed over the last echoes, none before the first one
+    rtt: Option<Duration>,
+}
+
+impl Connection {
+    /// A connection waiting for the acknowledgement of the peer
+    pub fn connecting(now: Instant) -> Connection {
+        return Connection { state: ConnectionState::Connecting, since: now, last_heard: now, last_keep_alive: now, rtt: None };
+    }
+
+    /// A connection the peer just asked for, so already acknowledged on this side
+    pub fn accepted(now: Instant) -> Connection {
+        return Connection { state: ConnectionState::Connected, ..Connection::connecting(now) };
+    }
+
+    pub fn state(&self) -> ConnectionState {
+        return self.state;
+    }
+
+    pub fn is_connected(&self) -> bool {
+        return self.state == ConnectionState::Connected;
+    }
+
+    /// The time a message takes to go to the peer and back, smoothed
+    pub fn rtt(&self) -> Option<Duration> {
+        return self.rtt;
+    }
+
+    pub fn last_heard(&self) -> Instant {
+        return self.last_heard;
+    }
+
+    /// The peer acknowledged the connection
+    pub fn acknowledged(&mut self, now: Instant) {
+        if self.state == ConnectionState::Connecting {
+            self.state = ConnectionState::Connected;
+            self.since = now;
+        }
+
+        self.heard(now);
+    }
+
+    /// To call for every message received from the peer
+    pub fn heard(&mut self, now: Instant) {
+        self.last_heard = self.last_heard.max(now);
+    }
+
+    /// The peer echoed the keep alive sent at `sent`
+    pub fn echoed(&mut self, sent: Instant, now: Instant) {
+        self.heard(now);
+
+        let sample = now.saturating_duration_since(sent);
+
+        // Like TCP, each new sample moves the estimate by an eighth, so a single late packet doesn't make it jump
+        self.rtt = Some(match self.rtt {
+            Some(rtt) => rtt * 7 / 8 + sample / 8,
+            None => sample,
+        });
+    }
+
+    /// If a keep alive has to be sent now, it is then counted as sent
+    pub fn keep_alive_due(&mut self, now: Instant) -> bool {
+        if !self.is_connected() || now.saturating_duration_since(self.last_keep_alive) < KEEP_ALIVE_RATE {
+            return false;
+        }
+
+        self.last_keep_alive = now;
+
+        return true;
+    }
+
+    /// Ends the connection, `Closed` when one of the sides left
+    pub fn close(&mut self, reason: DisconnectReason) {
+        if let ConnectionState::Disconnected(_) = self.state {
+            return;
+        }
+
+        self.state = ConnectionState::Disconnected(reason);
+    }
+
+    /// Times the connection out if the peer was silent for too long, returns the new state
+    pub fn update(&mut self, now: Instant) -> ConnectionState {
+        let silence = now.saturating_duration_since(self.last_heard);
+
+        let timed_out = match self.state {
+            ConnectionState::Connecting => now.saturating_duration_since(self.since) > CONNECT_TIMEOUT,
+            ConnectionState::Connected => silence > TIMEOUT,
+            ConnectionState::Disconnected(_) => false,
+        };
+
+        if timed_out {
+            self.close(DisconnectReason::TimedOut);
+        }
+
+        return self.state;
+    }
+}
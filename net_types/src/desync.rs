@@ -0,0 +1,95 @@
+use std::collections::VecDeque;
+
+use serde::{Serialize, Deserialize};
+
+use game_logic::{Player, EntityId, StateHash};
+
+use super::codec;
+
+/// The clients hash the ticks that are a multiple of this, about once a second
+pub const HASH_INTERVAL_TICKS: u64 = 60;
+
+/// The hash of what the server decides of its players at a tick, as its clients see it
+/// A client simulates the movement of its player and receives the others rounded, so only the health,
+/// the state and the invulnerability are hashed, rounded as they are sent
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct WorldHash {
+    pub tick: u64,
+    pub hash: u64,
+}
+
+impl WorldHash {
+    /// `players` are given with their id on the server, in any order
+    pub fn of_players<'a, I>(tick: u64, players: I) -> WorldHash where I: IntoIterator<Item = (EntityId, &'a Player)> {
+        let hash = players.into_iter().fold(0u64, |acc, (id, player)| acc.wrapping_add(shared_state(id, player).state_hash()));
+
+        return WorldHash { tick, hash };
+    }
+}
+
+/// The part of the player the server decides, the rest is left to its default
+fn shared_state(id: EntityId, player: &Player) -> Player {
+    let sent = codec::as_sent(player);
+
+    let mut shared = Player::new();
+    shared.id = id;
+    shared.health = sent.health;
+    shared.state = sent.state;
+    shared.invulnerable_until = sent.invulnerable_until;
+
+    return shared;
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HashCheck {
+    InSync,
+    /// The client diverged at or before this tick
+    Desync {
+        expected: u64,
+    },
+    /// The tick is too old or not simulated yet, nothing can be said
+    Unknown,
+}
+
+/// The hashes of the last hashed ticks of the server, to compare with the hashes sent by the clients
+/// The clients are always a bit behind, so a few of them are kept
+#[derive(Debug, Clone)]
+pub struct HashHistory {
+    hashes: VecDeque<WorldHash>,
+    capacity: usize,
+}
+
+impl HashHistory {
+    /// About 8 seconds of hashed ticks
+    pub const DEFAULT_CAPACITY: usize = 8;
+
+    pub fn new(capacity: usize) -> HashHistory {
+        return HashHistory { hashes: VecDeque::with_capacity(capacity), capacity };
+    }
+
+    pub fn push(&mut self, hash: WorldHash) {
+        if self.hashes.len() == self.capacity {
+            self.hashes.pop_front();
+        }
+
+        self.hashes.push_back(hash);
+    }
+
+    pub fn get(&self, tick: u64) -> Option<u64> {
+        return self.hashes.iter().rev().find(|hash| hash.tick == tick).map(|hash| hash.hash);
+    }
+
+    pub fn check(&self, other: &WorldHash) -> HashCheck {
+        return match self.get(other.tick) {
+            Some(hash) if hash == other.hash => HashCheck::InSync,
+            Some(hash) => HashCheck::Desync { expected: hash },
+            None => HashCheck::Unknown,
+        };
+    }
+}
+
+impl Default for HashHistory {
+    fn default() -> Self {
+        return HashHistory::new(Self::DEFAULT_CAPACITY);
+    }
+}
@@ -1,38 +1,67 @@
-use std::time::{Instant, Duration};
+use std::time::Duration;
 
 use serde::{Serialize, Deserialize};
 
-use game_logic::Player;
+use game_logic::{Player, PlayerInput, EntityId, WorldConfig, Asteroid, Projectile};
+
+pub mod connection;
+pub mod codec;
+pub mod clock;
+pub mod desync;
+
+pub use connection::{Connection, ConnectionState, DisconnectReason, KEEP_ALIVE_RATE, CONNECT_TIMEOUT};
+pub use clock::{Timestamp, ServerClock, ClockSync};
+pub use codec::{CodecError, PROTOCOL_VERSION};
+pub use desync::{WorldHash, HashHistory, HashCheck, HASH_INTERVAL_TICKS};
 
 #[derive(Debug, Clone, Copy, Serialize, Deserialize)]
 /// The big container this one gets serialized
 /// From client to server
 pub enum UpMsgBox {
-    NewConnection,
+    /// Sent again until acknowledged, with the look the player of the client will have
+    NewConnection {
+        look: PlayerLook,
+    },
+    /// Sent regularly, the server echoes it back with its own time, see `ClockSync`
     KeepAlive {
-        #[serde(with = "serde_millis")]
-        time: Instant,
+        /// On the clock of the client, not synced
+        time: Timestamp,
     },
+    /// The look of the player isn't sent, only the one given on connection counts
     PlayerUpdate {
         player: Player,
-        #[serde(with = "serde_millis")]
-        time: Instant,
+        /// When the player was in this state, older updates than the last one are ignored
+        time: Timestamp,
+    },
+    /// A new input of the player, to be applied at its tick
+    Input {
+        input: PlayerInput,
     },
     Disconect,
+    /// The players of the client at a tick of the server, for the server to check they are in sync
+    WorldHash(WorldHash),
 }
 
-#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 /// The big container this one gets serialized
 /// From server to client
 pub enum DownMsgBox {
     ConnectionAcknowleged {
         key: u64,
-        your_id: usize,
+        your_id: EntityId,
+        /// The seed of the server's world, the asteroids come with the chunk snapshots
+        world_seed: u64,
+        /// The config of the server's world, the client must simulate with the same one
+        world_config: WorldConfig,
+        /// A first time sync, as an answer to the connection request
+        server_time: Timestamp,
     },
     ServerClosing,
+    /// The echo of a keep alive of the client
     KeepAlive {
-        #[serde(with = "serde_millis")]
-        time: Instant,
+        /// The time the client sent
+        time: Timestamp,
+        server_time: Timestamp,
     },
     GameUpdate(GameUpdate),
     // If the server doesn't recognise the player
@@ -40,25 +69,103 @@ pub enum DownMsgBox {
     Unrecognised,
 }
 
-#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum GameUpdate {
+    /// The look of the player isn't sent, the one from `NewPlayer` stays
     PlayerUpdate {
-        id: usize,
+        id: EntityId,
         player: Player,
+        /// The start of the tick the player is from, older updates than the last one are ignored
+        time: Timestamp,
     },
-    AsteroidChunkGen {
-        pos: (i64, i64),
-        #[serde(with = "serde_millis")]
-        time: Instant,
-    },
+    /// The only update sending the look of the player
     NewPlayer {
-        id: usize,
+        id: EntityId,
         player: Player,
     },
     PlayerDisconnect {
-        id: usize,
+        id: EntityId,
+    },
+    /// A part of the asteroids of a chunk, with their ids on the server, see `GameUpdate::chunk_state`
+    /// Sent on connection and whenever the asteroids of the chunk change otherwise than by moving
+    ChunkState {
+        chunk: (i64, i64),
+        /// The start of the tick of the snapshot, older snapshots than the last one of the chunk are ignored
+        time: Timestamp,
+        part: u8,
+        n_parts: u8,
+        asteroids: Vec<Asteroid>,
+    },
+    /// A projectile fired on the server, its `expire_time` is the time it has left to live
+    NewProjectile {
+        projectile: Projectile,
     },
 }
 
+impl GameUpdate {
+    /// The snapshot of the asteroids of a chunk, in parts of at most `CHUNK_PART_ASTEROIDS`
+    /// An empty chunk still takes a part, telling its asteroids are gone
+    pub fn chunk_state(chunk: (i64, i64), time: Timestamp, asteroids: &[Asteroid]) -> Vec<GameUpdate> {
+        let parts: Vec<_> = asteroids.chunks(CHUNK_PART_ASTEROIDS).take(u8::MAX as usize).collect();
+        if parts.is_empty() {
+            return vec![GameUpdate::ChunkState { chunk, time, part: 0, n_parts: 1, asteroids: Vec::new() }];
+        }
+
+        let n_parts = parts.len() as u8;
+        return parts.into_iter().enumerate()
+            .map(|(part, asteroids)| GameUpdate::ChunkState { chunk, time, part: part as u8, n_parts, asteroids: asteroids.to_vec() })
+            .collect();
+    }
+}
+
 /// The max time for a client to not respond if more we disconnect to client
-pub const TIMEOUT: Duration = Duration::from_secs(1);
\ No newline at end of file
+pub const TIMEOUT: Duration = Duration::from_secs(1);
+
+/// The port the server listens on when no address is given
+pub const DEFAULT_PORT: u16 = 7878;
+
+/// The biggest datagram a message can take, bigger ones are cut by the receiving buffer
+pub const MAX_PACKET_SIZE: usize = 1400;
+
+/// The most asteroids a part of a chunk snapshot holds, to fit in a datagram
+pub const CHUNK_PART_ASTEROIDS: usize = 32;
+
+/// What a player looks like, chosen by its client and sent once when the player joins
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct PlayerLook {
+    pub accent_color_0: [f32; 4],
+    pub accent_color_1: [f32; 4],
+    pub accent_color_2: [f32; 4],
+    pub accent_color_3: [f32; 4],
+    pub accent_flame_color: [f32; 4],
+    pub player_img: i32,
+}
+
+impl PlayerLook {
+    pub fn of(player: &Player) -> PlayerLook {
+        return PlayerLook {
+            accent_color_0: player.accent_color_0,
+            accent_color_1: player.accent_color_1,
+            accent_color_2: player.accent_color_2,
+            accent_color_3: player.accent_color_3,
+            accent_flame_color: player.accent_flame_color,
+            player_img: player.player_img,
+        };
+    }
+
+    pub fn apply(&self, player: &mut Player) {
+        player.accent_color_0 = self.accent_color_0;
+        player.accent_color_1 = self.accent_color_1;
+        player.accent_color_2 = self.accent_color_2;
+        player.accent_color_3 = self.accent_color_3;
+        player.accent_flame_color = self.accent_flame_color;
+        player.player_img = self.player_img;
+    }
+}
+
+/// The look of a new player
+impl Default for PlayerLook {
+    fn default() -> Self {
+        return PlayerLook::of(&Player::new());
+    }
+}
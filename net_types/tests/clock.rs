@@ -0,0 +1,153 @@
+use std::time::{Duration, Instant};
+
+use game_logic::WORLD_UPD_RATE;
+use web_types::{Timestamp, ServerClock, ClockSync};
+
+/// Runs a time sync exchange: the request takes `up` to reach the server and the answer `down` to come back
+fn exchange(sync: &mut ClockSync, server: &ServerClock, start: Instant, up: Duration, down: Duration) -> Instant {
+    let sent = sync.local_time(start);
+    let server_time = server.now(start + up);
+    let now = start + up + down;
+    sync.sample(sent, server_time, now);
+
+    return now;
+}
+
+fn assert_close(a: Timestamp, b: Timestamp, tolerance: Duration) {
+    let diff = a.as_micros().abs_diff(b.as_micros());
+    assert!(diff <= tolerance.as_micros() as u64, "{:?} and {:?} are {} us apart", a, b, diff);
+}
+
+#[test]
+fn timestamps_follow_the_ticks() {
+    assert_eq!(Timestamp::of_tick(0), Timestamp::ZERO);
+    assert_eq!(Timestamp::of_tick(120).tick(), 120);
+    assert_eq!((Timestamp::of_tick(120) + WORLD_UPD_RATE / 2).tick(), 120);
+    assert_eq!(Timestamp::of_tick(121).saturating_duration_since(Timestamp::of_tick(120)).as_micros(), WORLD_UPD_RATE.as_micros());
+    assert_eq!(Timestamp::of_tick(120).saturating_duration_since(Timestamp::of_tick(121)), Duration::ZERO);
+    assert!(Timestamp::of_tick(120) < Timestamp::of_tick(121));
+}
+
+#[test]
+fn server_clock_is_driven_by_the_ticks() {
+    let start = Instant::now();
+    let mut clock = ServerClock::new(0, start);
+
+    assert_eq!(clock.now(start), Timestamp::ZERO);
+    assert_eq!(clock.now(start + Duration::from_millis(5)), Timestamp::from_micros(5_000));
+
+    // A late tick doesn't move the clock away from the tick numbers
+    clock.ticked(10, start + Duration::from_secs(1));
+    assert_eq!(clock.now(start + Duration::from_secs(1)), Timestamp::of_tick(10));
+    assert_eq!(clock.now(start + Duration::from_secs(1)).tick(), 10);
+}
+
+#[test]
+fn not_synced_before_the_first_exchange() {
+    let sync = ClockSync::new(Instant::now());
+
+    assert!(!sync.is_synced());
+    assert_eq!(sync.server_time(Instant::now()), None);
+}
+
+#[test]
+fn symmetric_exchanges_give_the_server_time() {
+    let start = Instant::now();
+    let mut sync = ClockSync::new(start);
+    // The server started long before the client
+    let server = ServerClock::new(100_000, start);
+
+    let latency = Duration::from_millis(40);
+    let now = exchange(&mut sync, &server, start + Duration::from_secs(1), latency, latency);
+
+    assert!(sync.is_synced());
+    assert_close(sync.server_time(now).unwrap(), server.now(now), Duration::from_micros(1));
+    assert_close(sync.server_time(now + Duration::from_secs(3)).unwrap(), server.now(now + Duration::from_secs(3)), Duration::from_micros(1));
+}
+
+#[test]
+fn delayed_exchanges_are_ignored() {
+    let start = Instant::now();
+    let mut sync = ClockSync::new(start);
+    let server = ServerClock::new(5_000, start);
+
+    let latency = Duration::from_millis(20);
+    let mut now = exchange(&mut sync, &server, start, latency, latency);
+
+    // An answer stuck on the way would put the clock far behind
+    now = exchange(&mut sync, &server, now, latency, Duration::from_millis(500));
+
+    assert_close(sync.server_time(now).unwrap(), server.now(now), Duration::from_micros(1));
+}
+
+#[test]
+fn better_exchanges_improve_the_estimate() {
+    let start = Instant::now();
+    let mut sync = ClockSync::new(start);
+    let server = ServerClock::new(5_000, start);
+
+    // The first answer, like the one to the connection request, took longer to come back than to go
+    let mut now = exchange(&mut sync, &server, start, Duration::from_millis(10), Duration::from_millis(90));
+    let first_error = sync.server_time(now).unwrap().as_micros().abs_diff(server.now(now).as_micros());
+    assert!(first_error > 30_000);
+
+    for _ in 0..20 {
+        now = exchange(&mut sync, &server, now + Duration::from_millis(250), Duration::from_millis(10), Duration::from_millis(12));
+    }
+
+    assert_close(sync.server_time(now).unwrap(), server.now(now), Duration::from_millis(2));
+}
+
+#[test]
+fn local_times_map_back_to_instants() {
+    let start = Instant::now();
+    let sync = ClockSync::new(start);
+
+    let later = start + Duration::from_millis(1234);
+    assert_eq!(sync.instant_of(sync.local_time(later)), Some(later));
+    // Out of range on some platforms, it must not panic
+    sync.instant_of(Timestamp::from_micros(u64::MAX));
+}
+
+#[test]
+fn answers_to_times_not_reached_yet_are_ignored() {
+    let start = Instant::now();
+    let mut sync = ClockSync::new(start);
+    let server = ServerClock::new(5_000, start);
+
+    let latency = Duration::from_millis(20);
+    let now = exchange(&mut sync, &server, start, latency, latency);
+    let synced = sync.server_time(now);
+
+    sync.sample(Timestamp::from_micros(u64::MAX), Timestamp::ZERO, now);
+    sync.sample(sync.local_time(now) + Duration::from_millis(1), Timestamp::ZERO, now);
+
+    assert_eq!(sync.server_time(now), synced);
+}
+
+#[test]
+fn hostile_server_times_do_not_overflow() {
+    let start = Instant::now();
+
+    for server_time in [Timestamp::ZERO, Timestamp::from_micros(u64::MAX), Timestamp::from_micros(i64::MAX as u64 + 1)] {
+        let mut sync = ClockSync::new(start);
+        let now = start + Duration::from_secs(1);
+
+        sync.sample(sync.local_time(start), server_time, now);
+        assert!(sync.is_synced());
+
+        // Close to the server time, without wrapping around
+        let read = sync.server_time(now + Duration::from_secs(1)).unwrap();
+        if server_time == Timestamp::ZERO {
+            assert!(read.as_micros() < 2_000_000);
+        } else {
+            assert!(read >= server_time);
+        }
+
+        // Then the server goes back to a sane time
+        for _ in 0..50 {
+            sync.sample(sync.local_time(now), Timestamp::ZERO, now);
+        }
+        sync.server_time(now).unwrap();
+    }
+}
@@ -0,0 +1,364 @@
+use std::f32::consts::TAU;
+use std::time::Duration;
+
+use cgmath::{Point2, Vector2};
+
+use game_logic::{Player, PlayerState, PlayerInput, EntityId, WorldConfig, DensityConfig, Asteroid, AsteroidSize, Projectile};
+use web_types::{UpMsgBox, DownMsgBox, GameUpdate, PlayerLook, WorldHash, Timestamp, CodecError, MAX_PACKET_SIZE, CHUNK_PART_ASTEROIDS, PROTOCOL_VERSION};
+
+/// A player with a value in every field the codec sends
+fn moving_player() -> Player {
+    let mut player = Player::new();
+
+    player.id = EntityId::from_parts(1, 0);
+    player.pos.x = -1234.567;
+    player.pos.y = 89.012;
+    player.vel.x = 0.75;
+    player.vel.y = -3.5;
+    player.rot = 2.5;
+    player.flame_frame = 0.75;
+    player.health = 42.5;
+    player.state = PlayerState::Dead { respawn_time: Duration::from_millis(123_456) };
+    player.invulnerable_until = Duration::from_millis(7_890);
+    player.accent_color_0 = [0.2, 0.4, 0.6, 0.8];
+    player.player_img = 2;
+
+    return player;
+}
+
+/// The biggest value of every field
+fn worst_player() -> Player {
+    let mut player = moving_player();
+    player.id = EntityId::NONE;
+    player.state = PlayerState::Dead { respawn_time: Duration::MAX };
+    player.invulnerable_until = Duration::MAX;
+
+    return player;
+}
+
+fn moving_asteroid(n: u32) -> Asteroid {
+    return Asteroid {
+        id: EntityId::from_parts(n, 2),
+        pos: Point2 { x: -56.25 + n as f64, y: 3.5 },
+        vel: Vector2 { x: 0.125, y: -0.03125 },
+        rot_speed: -1.75,
+        rot: 5.,
+        img_idx: 3,
+        spawn_time: 0.,
+        size: AsteroidSize::Large,
+    };
+}
+
+fn worst_asteroid() -> Asteroid {
+    return Asteroid { id: EntityId::NONE, ..moving_asteroid(0) };
+}
+
+fn moving_projectile() -> Projectile {
+    return Projectile {
+        id: EntityId::from_parts(9, 1),
+        pos: Point2 { x: 12.5, y: -0.75 },
+        vel: Vector2 { x: 1.5, y: 0.25 },
+        owner: EntityId::from_parts(1, 0),
+        expire_time: Duration::from_millis(1_150),
+    };
+}
+
+fn worst_input() -> PlayerInput {
+    return PlayerInput { tick: u64::MAX, player: EntityId::NONE, thrust: true, turn_left: true, turn_right: true, fire: true, brake: true };
+}
+
+/// The longest config to encode, too many octaves to be accepted
+fn worst_config() -> WorldConfig {
+    return WorldConfig {
+        density: DensityConfig::Noise { max_per_chunk: 8., scale: 12., void_level: 0.5, octaves: u32::MAX },
+        chunks_per_tick: usize::MAX,
+        chunk_store_capacity: usize::MAX,
+        ..WorldConfig::new()
+    };
+}
+
+/// A config the decoding accepts, with the biggest counts
+fn big_config() -> WorldConfig {
+    return WorldConfig {
+        chunks_per_tick: usize::MAX,
+        chunk_store_capacity: usize::MAX,
+        ..WorldConfig::new()
+    };
+}
+
+fn assert_same_motion(decoded: &Player, sent: &Player) {
+    assert_eq!(decoded.id, sent.id);
+    assert!((decoded.pos.x - sent.pos.x).abs() <= 1. / 4096.);
+    assert!((decoded.pos.y - sent.pos.y).abs() <= 1. / 4096.);
+    assert!((decoded.vel.x - sent.vel.x).abs() <= 1. / 65536.);
+    assert!((decoded.vel.y - sent.vel.y).abs() <= 1. / 65536.);
+    assert!((decoded.rot - sent.rot.rem_euclid(TAU)).abs() <= TAU / 65536.);
+    assert_eq!(decoded.flame_frame, sent.flame_frame);
+    assert_eq!(decoded.health, sent.health);
+    assert_eq!(decoded.state, sent.state);
+    assert_eq!(decoded.invulnerable_until, sent.invulnerable_until);
+}
+
+/// The colours lose their precision past a byte per channel
+fn assert_same_look(decoded: &PlayerLook, sent: &PlayerLook) {
+    let colors = |look: &PlayerLook| [look.accent_color_0, look.accent_color_1, look.accent_color_2, look.accent_color_3, look.accent_flame_color];
+
+    for (decoded, sent) in colors(decoded).iter().flatten().zip(colors(sent).iter().flatten()) {
+        assert!((decoded - sent).abs() < 1. / 255.);
+    }
+    assert_eq!(decoded.player_img, sent.player_img);
+}
+
+fn up_round_trip(msg: UpMsgBox) -> UpMsgBox {
+    let bytes = msg.to_bytes();
+    assert!(bytes.len() <= msg.size_budget(), "{:?} takes {} bytes", msg, bytes.len());
+
+    return UpMsgBox::from_bytes(&bytes).unwrap();
+}
+
+fn down_round_trip(msg: DownMsgBox) -> DownMsgBox {
+    let bytes = msg.to_bytes();
+    assert!(bytes.len() <= msg.size_budget(), "{:?} takes {} bytes", msg, bytes.len());
+
+    return DownMsgBox::from_bytes(&bytes).unwrap();
+}
+
+#[test]
+fn up_messages_round_trip() {
+    let look = PlayerLook::of(&moving_player());
+    match up_round_trip(UpMsgBox::NewConnection { look }) {
+        UpMsgBox::NewConnection { look: decoded } => assert_same_look(&decoded, &look),
+        msg => panic!("{:?}", msg),
+    }
+
+    let player = moving_player();
+    match up_round_trip(UpMsgBox::PlayerUpdate { player, time: Timestamp::of_tick(600) }) {
+        UpMsgBox::PlayerUpdate { player: decoded, time } => {
+            assert_same_motion(&decoded, &player);
+            assert_eq!(time, Timestamp::of_tick(600));
+        },
+        msg => panic!("{:?}", msg),
+    }
+
+    let input = PlayerInput { tick: 300, player: player.id, thrust: true, fire: true, ..Default::default() };
+    match up_round_trip(UpMsgBox::Input { input }) {
+        UpMsgBox::Input { input: decoded } => assert_eq!(decoded, input),
+        msg => panic!("{:?}", msg),
+    }
+
+    let hash = WorldHash { tick: 12, hash: 0xdead_beef_cafe_f00d };
+    match up_round_trip(UpMsgBox::WorldHash(hash)) {
+        UpMsgBox::WorldHash(decoded) => assert_eq!(decoded, hash),
+        msg => panic!("{:?}", msg),
+    }
+
+    assert!(matches!(up_round_trip(UpMsgBox::Disconect), UpMsgBox::Disconect));
+}
+
+#[test]
+fn down_messages_round_trip() {
+    let config = big_config();
+    let server_time = Timestamp::from_micros(123_456_789);
+    match down_round_trip(DownMsgBox::ConnectionAcknowleged { key: 77, your_id: EntityId::from_parts(3, 1), world_seed: 99, world_config: config, server_time }) {
+        DownMsgBox::ConnectionAcknowleged { key, your_id, world_seed, world_config, server_time: decoded } => {
+            assert_eq!((key, your_id, world_seed, decoded), (77, EntityId::from_parts(3, 1), 99, server_time));
+            assert_eq!(world_config, config);
+        },
+        msg => panic!("{:?}", msg),
+    }
+
+    let player = moving_player();
+    match down_round_trip(DownMsgBox::GameUpdate(GameUpdate::NewPlayer { id: player.id, player })) {
+        DownMsgBox::GameUpdate(GameUpdate::NewPlayer { id, player: decoded }) => {
+            assert_eq!(id, player.id);
+            assert_same_motion(&decoded, &player);
+            assert_same_look(&PlayerLook::of(&decoded), &PlayerLook::of(&player));
+        },
+        msg => panic!("{:?}", msg),
+    }
+
+    match down_round_trip(DownMsgBox::GameUpdate(GameUpdate::PlayerDisconnect { id: player.id })) {
+        DownMsgBox::GameUpdate(GameUpdate::PlayerDisconnect { id }) => assert_eq!(id, player.id),
+        msg => panic!("{:?}", msg),
+    }
+
+    assert!(matches!(down_round_trip(DownMsgBox::ServerClosing), DownMsgBox::ServerClosing));
+    assert!(matches!(down_round_trip(DownMsgBox::Unrecognised), DownMsgBox::Unrecognised));
+}
+
+#[test]
+fn world_updates_round_trip() {
+    let asteroids: Vec<_> = (0..CHUNK_PART_ASTEROIDS as u32 + 5).map(moving_asteroid).collect();
+    let time = Timestamp::of_tick(600);
+    let parts = GameUpdate::chunk_state((-3, 7), time, &asteroids);
+    assert_eq!(parts.len(), 2);
+
+    let mut decoded_asteroids = Vec::new();
+    for (i, update) in parts.into_iter().enumerate() {
+        match down_round_trip(DownMsgBox::GameUpdate(update)) {
+            DownMsgBox::GameUpdate(GameUpdate::ChunkState { chunk, time: decoded, part, n_parts, asteroids }) => {
+                assert_eq!((chunk, decoded, part as usize, n_parts), ((-3, 7), time, i, 2));
+                decoded_asteroids.extend(asteroids);
+            },
+            msg => panic!("{:?}", msg),
+        }
+    }
+
+    assert_eq!(decoded_asteroids.len(), asteroids.len());
+    for (decoded, sent) in decoded_asteroids.iter().zip(&asteroids) {
+        assert_eq!(decoded.id, sent.id);
+        assert_eq!((decoded.pos, decoded.vel), (sent.pos, sent.vel));
+        assert!((decoded.rot - sent.rot).abs() <= TAU / 65536.);
+        assert_eq!((decoded.rot_speed, decoded.img_idx, decoded.size), (sent.rot_speed, sent.img_idx, sent.size));
+    }
+
+    // An empty chunk still tells its asteroids are gone
+    match GameUpdate::chunk_state((0, 0), time, &[]).as_slice() {
+        [GameUpdate::ChunkState { part: 0, n_parts: 1, asteroids, .. }] => assert!(asteroids.is_empty()),
+        parts => panic!("{:?}", parts),
+    }
+
+    let projectile = moving_projectile();
+    match down_round_trip(DownMsgBox::GameUpdate(GameUpdate::NewProjectile { projectile })) {
+        DownMsgBox::GameUpdate(GameUpdate::NewProjectile { projectile: decoded }) => {
+            assert_eq!((decoded.id, decoded.owner, decoded.expire_time), (projectile.id, projectile.owner, projectile.expire_time));
+            assert_eq!((decoded.pos, decoded.vel), (projectile.pos, projectile.vel));
+        },
+        msg => panic!("{:?}", msg),
+    }
+}
+
+#[test]
+fn colours_are_only_sent_on_join() {
+    let player = moving_player();
+
+    let update = DownMsgBox::GameUpdate(GameUpdate::PlayerUpdate { id: player.id, player, time: Timestamp::of_tick(60) });
+    let joined = DownMsgBox::GameUpdate(GameUpdate::NewPlayer { id: player.id, player });
+    assert!(update.to_bytes().len() < joined.to_bytes().len());
+
+    match down_round_trip(update) {
+        DownMsgBox::GameUpdate(GameUpdate::PlayerUpdate { player: decoded, .. }) => {
+            assert_same_motion(&decoded, &player);
+            assert_eq!(PlayerLook::of(&decoded), PlayerLook::default());
+        },
+        msg => panic!("{:?}", msg),
+    }
+}
+
+#[test]
+fn every_input_round_trips() {
+    for flags in 0..32_u8 {
+        let bit = |bit: u8| flags & (1 << bit) != 0;
+        let input = PlayerInput { tick: 1 << 40, player: EntityId::from_parts(200, 3), thrust: bit(0), turn_left: bit(1), turn_right: bit(2), fire: bit(3), brake: bit(4) };
+
+        match up_round_trip(UpMsgBox::Input { input }) {
+            UpMsgBox::Input { input: decoded } => assert_eq!(decoded, input),
+            msg => panic!("{:?}", msg),
+        }
+    }
+}
+
+#[test]
+fn keep_alive_times_come_back() {
+    let sent = Timestamp::from_micros(500_000);
+
+    match up_round_trip(UpMsgBox::KeepAlive { time: sent }) {
+        UpMsgBox::KeepAlive { time } => assert_eq!(time, sent),
+        msg => panic!("{:?}", msg),
+    }
+
+    match down_round_trip(DownMsgBox::KeepAlive { time: sent, server_time: Timestamp::of_tick(1_000_000) }) {
+        DownMsgBox::KeepAlive { time, server_time } => assert_eq!((time, server_time), (sent, Timestamp::of_tick(1_000_000))),
+        msg => panic!("{:?}", msg),
+    }
+}
+
+#[test]
+fn messages_fit_their_budget() {
+    let player = worst_player();
+    let up = [
+        UpMsgBox::NewConnection { look: PlayerLook::default() },
+        UpMsgBox::KeepAlive { time: Timestamp::from_micros(u64::MAX) },
+        UpMsgBox::PlayerUpdate { player, time: Timestamp::from_micros(u64::MAX) },
+        UpMsgBox::Input { input: worst_input() },
+        UpMsgBox::Disconect,
+        UpMsgBox::WorldHash(WorldHash { tick: u64::MAX, hash: u64::MAX }),
+    ];
+    let down = [
+        DownMsgBox::ConnectionAcknowleged { key: u64::MAX, your_id: EntityId::NONE, world_seed: u64::MAX, world_config: worst_config(), server_time: Timestamp::from_micros(u64::MAX) },
+        DownMsgBox::ServerClosing,
+        DownMsgBox::KeepAlive { time: Timestamp::from_micros(u64::MAX), server_time: Timestamp::from_micros(u64::MAX) },
+        DownMsgBox::GameUpdate(GameUpdate::PlayerUpdate { id: EntityId::NONE, player, time: Timestamp::from_micros(u64::MAX) }),
+        DownMsgBox::GameUpdate(GameUpdate::NewPlayer { id: EntityId::NONE, player }),
+        DownMsgBox::GameUpdate(GameUpdate::PlayerDisconnect { id: EntityId::NONE }),
+        DownMsgBox::GameUpdate(GameUpdate::ChunkState {
+            chunk: (i64::MIN, i64::MAX),
+            time: Timestamp::from_micros(u64::MAX),
+            part: u8::MAX,
+            n_parts: u8::MAX,
+            asteroids: vec![worst_asteroid(); CHUNK_PART_ASTEROIDS],
+        }),
+        DownMsgBox::GameUpdate(GameUpdate::NewProjectile { projectile: Projectile { id: EntityId::NONE, owner: EntityId::NONE, expire_time: Duration::MAX, ..moving_projectile() } }),
+        DownMsgBox::Unrecognised,
+    ];
+
+    // The worst cases reach the budget exactly, so it stays accurate
+    for msg in up {
+        assert_eq!(msg.to_bytes().len(), msg.size_budget(), "{:?}", msg);
+        assert!(msg.size_budget() <= MAX_PACKET_SIZE);
+    }
+    for msg in down {
+        assert_eq!(msg.to_bytes().len(), msg.size_budget(), "{:?}", msg);
+        assert!(msg.size_budget() <= MAX_PACKET_SIZE);
+    }
+
+    // The update sent for every player at every tick, for a usual player a minute into the game
+    let mut player = moving_player();
+    player.state = PlayerState::Alive;
+    let update = DownMsgBox::GameUpdate(GameUpdate::PlayerUpdate { id: player.id, player, time: Timestamp::of_tick(3600) });
+    assert!(update.to_bytes().len() <= 40);
+}
+
+#[test]
+fn other_versions_are_rejected() {
+    let mut bytes = UpMsgBox::Disconect.to_bytes();
+    assert_eq!(bytes[0], PROTOCOL_VERSION);
+
+    bytes[0] = PROTOCOL_VERSION.wrapping_add(1);
+    assert_eq!(UpMsgBox::from_bytes(&bytes).unwrap_err(), CodecError::Version { got: PROTOCOL_VERSION.wrapping_add(1) });
+}
+
+#[test]
+fn broken_datagrams_are_rejected() {
+    let bytes = DownMsgBox::GameUpdate(GameUpdate::NewPlayer { id: EntityId::from_parts(1, 0), player: moving_player() }).to_bytes();
+
+    for len in 0..bytes.len() {
+        assert!(DownMsgBox::from_bytes(&bytes[..len]).is_err(), "{} bytes were accepted", len);
+    }
+
+    let mut longer = bytes.clone();
+    longer.push(0);
+    assert_eq!(DownMsgBox::from_bytes(&longer).unwrap_err(), CodecError::TrailingBytes(1));
+
+    let chunk = DownMsgBox::GameUpdate(GameUpdate::chunk_state((-1, 1), Timestamp::ZERO, &[moving_asteroid(1), moving_asteroid(2)]).remove(0)).to_bytes();
+    for len in 0..chunk.len() {
+        assert!(DownMsgBox::from_bytes(&chunk[..len]).is_err(), "{} bytes of a chunk were accepted", len);
+    }
+
+    assert_eq!(UpMsgBox::from_bytes(&[PROTOCOL_VERSION, 200]).unwrap_err(), CodecError::UnknownTag { what: "UpMsgBox", tag: 200 });
+}
+
+#[test]
+fn invalid_configs_are_rejected() {
+    let ack = |world_config| DownMsgBox::ConnectionAcknowleged { key: 1, your_id: EntityId::NONE, world_seed: 0, world_config, server_time: Timestamp::ZERO };
+
+    let configs = [
+        worst_config(),
+        WorldConfig { chunk_size: 0., ..WorldConfig::new() },
+        WorldConfig { chunk_load_dist: 1e300, chunk_unload_dist: 1e300, ..WorldConfig::new() },
+    ];
+
+    for config in configs {
+        assert_eq!(DownMsgBox::from_bytes(&ack(config).to_bytes()).unwrap_err(), CodecError::InvalidConfig);
+    }
+}
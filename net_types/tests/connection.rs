@@ -0,0 +1,92 @@
+use std::time::{Duration, Instant};
+
+use web_types::{Connection, ConnectionState, DisconnectReason, TIMEOUT, KEEP_ALIVE_RATE, CONNECT_TIMEOUT};
+
+fn ms(n: u64) -> Duration {
+    return Duration::from_millis(n);
+}
+
+#[test]
+fn connects_when_acknowledged() {
+    let start = Instant::now();
+    let mut connection = Connection::connecting(start);
+    assert_eq!(connection.update(start + ms(100)), ConnectionState::Connecting);
+
+    connection.acknowledged(start + ms(200));
+    assert_eq!(connection.update(start + ms(300)), ConnectionState::Connected);
+}
+
+#[test]
+fn connecting_times_out() {
+    let start = Instant::now();
+    let mut connection = Connection::connecting(start);
+
+    assert_eq!(connection.update(start + CONNECT_TIMEOUT + ms(1)), ConnectionState::Disconnected(DisconnectReason::TimedOut));
+}
+
+#[test]
+fn silent_peer_times_out() {
+    let start = Instant::now();
+    let mut connection = Connection::accepted(start);
+
+    // Every message pushes the timeout back
+    connection.heard(start + TIMEOUT);
+    assert_eq!(connection.update(start + TIMEOUT + ms(500)), ConnectionState::Connected);
+
+    assert_eq!(connection.update(start + TIMEOUT * 2 + ms(1)), ConnectionState::Disconnected(DisconnectReason::TimedOut));
+}
+
+#[test]
+fn closed_connection_stays_closed() {
+    let start = Instant::now();
+    let mut connection = Connection::accepted(start);
+
+    connection.close(DisconnectReason::Refused);
+    connection.close(DisconnectReason::Closed);
+    connection.heard(start + ms(10));
+    connection.acknowledged(start + ms(10));
+
+    assert_eq!(connection.update(start + TIMEOUT * 10), ConnectionState::Disconnected(DisconnectReason::Refused));
+}
+
+#[test]
+fn keep_alives_follow_their_rate() {
+    let start = Instant::now();
+    let mut connection = Connection::accepted(start);
+
+    let sent = (1..=100).filter(|i| connection.keep_alive_due(start + ms(*i * 10))).count();
+
+    // A second at most one every rate
+    assert_eq!(sent, (Duration::from_secs(1).as_millis() / KEEP_ALIVE_RATE.as_millis()) as usize);
+}
+
+#[test]
+fn no_keep_alive_before_connected() {
+    let start = Instant::now();
+    let mut connection = Connection::connecting(start);
+
+    assert!(!connection.keep_alive_due(start + KEEP_ALIVE_RATE * 2));
+}
+
+#[test]
+fn rtt_is_measured_from_the_echoes() {
+    let start = Instant::now();
+    let mut connection = Connection::accepted(start);
+    assert_eq!(connection.rtt(), None);
+
+    connection.echoed(start, start + ms(80));
+    assert_eq!(connection.rtt(), Some(ms(80)));
+
+    // A single late echo only moves the estimate a bit
+    connection.echoed(start + ms(100), start + ms(500));
+    let rtt = connection.rtt().unwrap();
+    assert!(rtt > ms(80) && rtt < ms(150), "{:?}", rtt);
+
+    // And steady echoes bring it to their value
+    for i in 0..100 {
+        let sent = start + ms(1000 + i * 100);
+        connection.echoed(sent, sent + ms(40));
+    }
+    let rtt = connection.rtt().unwrap();
+    assert!(rtt >= ms(39) && rtt <= ms(41), "{:?}", rtt);
+}
@@ -0,0 +1,73 @@
+use std::time::Duration;
+
+use game_logic::{Player, PlayerState, EntityId};
+use web_types::{DownMsgBox, GameUpdate, WorldHash, HashHistory, HashCheck};
+
+fn hurt_player() -> Player {
+    let mut player = Player::new();
+    player.health = 100. / 3.;
+    player.state = PlayerState::Dead { respawn_time: Duration::from_micros(1_234_567) };
+    player.invulnerable_until = Duration::from_micros(7_654_321);
+
+    return player;
+}
+
+/// The player as the client gets it
+fn received(player: Player) -> Player {
+    let bytes = DownMsgBox::GameUpdate(GameUpdate::NewPlayer { id: player.id, player }).to_bytes();
+
+    return match DownMsgBox::from_bytes(&bytes).unwrap() {
+        DownMsgBox::GameUpdate(GameUpdate::NewPlayer { player, .. }) => player,
+        msg => panic!("{:?}", msg),
+    };
+}
+
+#[test]
+fn clients_hash_what_the_server_sent() {
+    let (a, b) = (EntityId::from_parts(1, 0), EntityId::from_parts(2, 0));
+    let player = hurt_player();
+    let server = WorldHash::of_players(60, [(a, &player), (b, &Player::new())]);
+
+    // Rounded by the codec, moved by the client and listed in another order
+    let mut moved = received(player);
+    moved.pos.x += 1.;
+    moved.vel.y = 2.;
+    moved.rot = 3.;
+    let client = WorldHash::of_players(60, [(b, &Player::new()), (a, &moved)]);
+
+    assert_eq!(client, server);
+}
+
+#[test]
+fn what_the_server_decides_changes_the_hash() {
+    let id = EntityId::from_parts(1, 0);
+    let player = hurt_player();
+    let hash = WorldHash::of_players(60, [(id, &player)]);
+
+    let mut healthier = player;
+    healthier.health += 1.;
+    let mut alive = player;
+    alive.state = PlayerState::Alive;
+    let mut vulnerable = player;
+    vulnerable.invulnerable_until = Duration::ZERO;
+
+    for other in [healthier, alive, vulnerable] {
+        assert_ne!(WorldHash::of_players(60, [(id, &other)]), hash);
+    }
+    assert_ne!(WorldHash::of_players(60, [(EntityId::from_parts(2, 0), &player)]), hash);
+    assert_ne!(WorldHash::of_players(60, [(id, &player), (EntityId::from_parts(2, 0), &player)]), hash);
+}
+
+#[test]
+fn history_checks_the_ticks_it_kept() {
+    let mut history = HashHistory::new(2);
+    for tick in [60, 120, 180] {
+        history.push(WorldHash { tick, hash: tick * 7 });
+    }
+
+    assert_eq!(history.check(&WorldHash { tick: 120, hash: 840 }), HashCheck::InSync);
+    assert_eq!(history.check(&WorldHash { tick: 180, hash: 0 }), HashCheck::Desync { expected: 1260 });
+    // Forgotten, and not simulated yet
+    assert_eq!(history.check(&WorldHash { tick: 60, hash: 420 }), HashCheck::Unknown);
+    assert_eq!(history.check(&WorldHash { tick: 240, hash: 0 }), HashCheck::Unknown);
+}
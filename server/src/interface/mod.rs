@@ -0,0 +1,321 @@
+use std::io::ErrorKind;
+use std::net::{SocketAddr, UdpSocket};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Instant;
+
+use fnv::{FnvHashMap as HashMap, FnvHashSet as HashSet};
+
+use game_logic::{World, Player, PlayerInput, Projectile, EntityId, WORLD_UPD_RATE};
+use web_types::{UpMsgBox, DownMsgBox, GameUpdate, PlayerLook, WorldHash, HashHistory, HashCheck, HASH_INTERVAL_TICKS, Connection, ConnectionState, Timestamp, ServerClock, MAX_PACKET_SIZE};
+
+/// A connected client
+#[derive(Debug, Clone, Copy)]
+struct Client {
+    key: u64,
+    player: EntityId,
+    connection: Connection,
+    /// The time of the last player update applied, the older ones arriving late are ignored
+    last_update: Timestamp,
+}
+
+/// Runs the authoritative world and keeps the clients in sync with it over UDP
+pub struct Server {
+    socket: UdpSocket,
+    world: World,
+    clients: HashMap<SocketAddr, Client>,
+    /// The protocol clock, every timestamp sent is read on it
+    clock: ServerClock,
+    /// The hashes the clients send are checked against these to detect desyncs
+    hashes: HashHistory,
+    /// The projectiles the clients were told about, until they expire
+    sent_projectiles: HashSet<EntityId>,
+    /// The position in the active chunks of the next chunk sent again, to heal the lost snapshots
+    refreshed_chunk: usize,
+    /// Set from another thread, like a signal handler, to make `run` close the server
+    stop: Arc<AtomicBool>,
+}
+
+impl Server {
+    pub fn bind(addr: SocketAddr, world: World) -> std::io::Result<Server> {
+        let socket = UdpSocket::bind(addr)?;
+
+        let clock = ServerClock::new(world.tick(), Instant::now());
+
+        return Ok(Server {
+            socket,
+            world,
+            clients: HashMap::default(),
+            clock,
+            hashes: HashHistory::default(),
+            sent_projectiles: HashSet::default(),
+            refreshed_chunk: 0,
+            stop: Arc::new(AtomicBool::new(false)),
+        });
+    }
+
+    pub fn local_addr(&self) -> std::io::Result<SocketAddr> {
+        return self.socket.local_addr();
+    }
+
+    /// Setting it stops `run` within a tick, the clients are told the server is closing
+    pub fn stop_flag(&self) -> Arc<AtomicBool> {
+        return self.stop.clone();
+    }
+
+    /// Runs the world at `WORLD_UPD_RATE`, handling the messages between the ticks, until `n_ticks` are simulated if given
+    /// or until stopped by the `stop_flag`
+    pub fn run(&mut self, n_ticks: Option<u64>) -> std::io::Result<()> {
+        let mut next_tick = Instant::now() + WORLD_UPD_RATE;
+        let mut buf = [0; MAX_PACKET_SIZE];
+
+        while n_ticks.is_none_or(|n_ticks| self.world.tick() < n_ticks) && !self.stop.load(Ordering::Relaxed) {
+            let now = Instant::now();
+
+            if now >= next_tick {
+                self.tick();
+                next_tick += WORLD_UPD_RATE;
+
+                // Too far behind to catch up, like after the process was suspended, the lost time is skipped
+                if now > next_tick + WORLD_UPD_RATE * 10 {
+                    next_tick = now + WORLD_UPD_RATE;
+                }
+                continue;
+            }
+
+            // Waits for a message at most until the next tick
+            self.socket.set_read_timeout(Some(next_tick - now))?;
+
+            match self.socket.recv_from(&mut buf) {
+                Ok((len, addr)) => self.handle_packet(&buf[..len], addr),
+                Err(err) if matches!(err.kind(), ErrorKind::WouldBlock | ErrorKind::TimedOut) => (),
+                // A signal arrived during the wait, the loop sees if it stopped the server
+                Err(err) if err.kind() == ErrorKind::Interrupted => (),
+                // On some platforms a client closing its socket makes the next receive fail, it isn't the server's problem
+                Err(err) if err.kind() == ErrorKind::ConnectionReset => (),
+                Err(err) => return Err(err),
+            }
+        }
+
+        self.close();
+
+        return Ok(());
+    }
+
+    /// Tells every client the server is going away
+    pub fn close(&mut self) {
+        self.broadcast(DownMsgBox::ServerClosing);
+        self.clients.clear();
+    }
+
+    /// Simulates a tick and sends its result to the clients
+    fn tick(&mut self) {
+        self.world.step();
+        self.clock.ticked(self.world.tick(), Instant::now());
+
+        self.drop_timed_out();
+
+        // The clients hash the players as this tick sends them
+        let tick = self.world.tick();
+        if tick.is_multiple_of(HASH_INTERVAL_TICKS) {
+            self.hashes.push(WorldHash::of_players(tick, self.world.players.iter().map(|player| (player.id, player))));
+        }
+
+        let time = Timestamp::of_tick(tick);
+        for player in self.world.players.clone() {
+            self.broadcast(DownMsgBox::GameUpdate(GameUpdate::PlayerUpdate { id: player.id, player, time }));
+        }
+
+        let mut chunks = self.world.changed_chunks().to_vec();
+        // A snapshot lost on the way is healed when its chunk comes around again
+        let active = self.world.active_chunks();
+        if !active.is_empty() {
+            self.refreshed_chunk = (self.refreshed_chunk + 1) % active.len();
+            chunks.push(active[self.refreshed_chunk]);
+        }
+        for update in chunks.into_iter().flat_map(|chunk| self.chunk_state(chunk, time)) {
+            self.broadcast(DownMsgBox::GameUpdate(update));
+        }
+
+        self.send_new_projectiles();
+    }
+
+    fn chunk_state(&self, chunk: (i64, i64), time: Timestamp) -> Vec<GameUpdate> {
+        let asteroids: Vec<_> = self.world.chunk_asteroids(chunk).into_iter().copied().collect();
+        return GameUpdate::chunk_state(chunk, time, &asteroids);
+    }
+
+    /// The clients simulate the projectiles from when they were fired, with the time they have left
+    fn send_new_projectiles(&mut self) {
+        let now = self.world.time();
+        let new: Vec<_> = self.world.projectiles.iter().filter(|projectile| !self.sent_projectiles.contains(&projectile.id)).copied().collect();
+        self.sent_projectiles.retain(|id| self.world.projectile(*id).is_some());
+
+        for projectile in new {
+            self.sent_projectiles.insert(projectile.id);
+            self.broadcast(DownMsgBox::GameUpdate(GameUpdate::NewProjectile {
+                projectile: Projectile { expire_time: projectile.expire_time.saturating_sub(now), ..projectile },
+            }));
+        }
+    }
+
+    fn handle_packet(&mut self, bytes: &[u8], addr: SocketAddr) {
+        let msg = match UpMsgBox::from_bytes(bytes) {
+            Ok(msg) => msg,
+            Err(err) => {
+                logger::warn(3, format!("Invalid message from {}: {}", addr, err));
+                return;
+            },
+        };
+
+        if let UpMsgBox::NewConnection { look } = msg {
+            self.connect(addr, look);
+            return;
+        }
+
+        let client = match self.clients.get_mut(&addr) {
+            Some(client) => client,
+            None => {
+                self.send(addr, DownMsgBox::Unrecognised);
+                return;
+            },
+        };
+        client.connection.heard(Instant::now());
+
+        // UDP doesn't keep the order, an update older than the last one applied is outdated
+        if let UpMsgBox::PlayerUpdate { time, .. } = msg {
+            if time < client.last_update {
+                return;
+            }
+            client.last_update = time;
+        }
+        let client = *client;
+
+        match msg {
+            UpMsgBox::NewConnection { .. } => (),
+            // Echoed with the time of the server, the client measures the round trip time and syncs its clock from it
+            UpMsgBox::KeepAlive { time } => self.send(addr, DownMsgBox::KeepAlive { time, server_time: self.clock.now(Instant::now()) }),
+            UpMsgBox::PlayerUpdate { player, .. } => self.apply_player_update(client.player, &player),
+            // A client only controls its own player, and its ticks don't match ours so the input applies on our next one
+            UpMsgBox::Input { input } => self.world.queue_input(PlayerInput { tick: self.world.tick() + 1, player: client.player, ..input }),
+            UpMsgBox::Disconect => self.disconnect(addr),
+            UpMsgBox::WorldHash(hash) => self.check_hash(addr, hash),
+        }
+    }
+
+    /// Gives a player to the client, or acknowledges again if the first acknowledgement was lost
+    fn connect(&mut self, addr: SocketAddr, look: PlayerLook) {
+        let (client, joined) = match self.clients.get(&addr) {
+            Some(client) => (*client, false),
+            None => {
+                let mut joining = Player::new();
+                look.apply(&mut joining);
+                let player = self.world.add_player(joining);
+                let client = Client {
+                    key: rand::random(),
+                    player,
+                    connection: Connection::accepted(Instant::now()),
+                    last_update: Timestamp::ZERO,
+                };
+                self.clients.insert(addr, client);
+
+                logger::info(3, format!("{} connected as player {}", addr, player));
+
+                // The others learn about the newcomer
+                if let Some(new_player) = self.world.player(player).copied() {
+                    self.broadcast_except(addr, DownMsgBox::GameUpdate(GameUpdate::NewPlayer { id: player, player: new_player }));
+                }
+
+                (client, true)
+            },
+        };
+
+        self.send(addr, DownMsgBox::ConnectionAcknowleged {
+            key: client.key,
+            your_id: client.player,
+            world_seed: self.world.seed(),
+            world_config: *self.world.config(),
+            server_time: self.clock.now(Instant::now()),
+        });
+
+        // The client ignores the updates until it is acknowledged
+        if joined {
+            self.welcome(addr, client.player);
+        }
+    }
+
+    /// Tells a newcomer about the other players and the asteroids
+    fn welcome(&self, addr: SocketAddr, player: EntityId) {
+        for other in &self.world.players {
+            if other.id != player {
+                self.send(addr, DownMsgBox::GameUpdate(GameUpdate::NewPlayer { id: other.id, player: *other }));
+            }
+        }
+
+        let time = Timestamp::of_tick(self.world.tick());
+        for update in self.world.active_chunks().into_iter().flat_map(|chunk| self.chunk_state(chunk, time)) {
+            self.send(addr, DownMsgBox::GameUpdate(update));
+        }
+    }
+
+    fn disconnect(&mut self, addr: SocketAddr) {
+        if let Some(client) = self.clients.remove(&addr) {
+            self.world.remove_player(client.player);
+            self.broadcast(DownMsgBox::GameUpdate(GameUpdate::PlayerDisconnect { id: client.player }));
+
+            logger::info(3, format!("{} disconnected", addr));
+        }
+    }
+
+    fn drop_timed_out(&mut self) {
+        let now = Instant::now();
+        let mut timed_out: Vec<_> = self.clients.iter_mut()
+            .filter_map(|(addr, client)| (client.connection.update(now) != ConnectionState::Connected).then_some(*addr))
+            .collect();
+        timed_out.sort_unstable();
+
+        for addr in timed_out {
+            logger::info(3, format!("{} timed out", addr));
+            self.disconnect(addr);
+        }
+    }
+
+    /// Takes the movement of the player from the client, the server stays in charge of its health and state
+    /// The look was given on connection, the updates don't carry it
+    fn apply_player_update(&mut self, id: EntityId, update: &Player) {
+        if let Some(player) = self.world.player_mut(id) {
+            if !player.is_alive() {
+                return;
+            }
+
+            player.pos = update.pos;
+            player.vel = update.vel;
+            player.rot = update.rot;
+            player.flame_frame = update.flame_frame;
+        }
+    }
+
+    fn check_hash(&self, addr: SocketAddr, hash: WorldHash) {
+        if let HashCheck::Desync { expected } = self.hashes.check(&hash) {
+            logger::warn(3, format!("{} desynced at tick {}, expected state {:016x} but got {:016x}", addr, hash.tick, expected, hash.hash));
+        }
+    }
+
+    fn send(&self, addr: SocketAddr, msg: DownMsgBox) {
+        if let Err(err) = self.socket.send_to(&msg.to_bytes(), addr) {
+            logger::error(3, format!("Unable to send to {}: {}", addr, err));
+        }
+    }
+
+    fn broadcast(&self, msg: DownMsgBox) {
+        for addr in self.clients.keys() {
+            self.send(*addr, msg.clone());
+        }
+    }
+
+    fn broadcast_except(&self, except: SocketAddr, msg: DownMsgBox) {
+        for addr in self.clients.keys().filter(|addr| **addr != except) {
+            self.send(*addr, msg.clone());
+        }
+    }
+}
@@ -1,15 +1,82 @@
-use std::time::Duration;
+use std::net::SocketAddr;
+use std::sync::atomic::Ordering;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 
-use game_logic::World;
+use game_logic::{World, WorldConfig};
+use web_types::DEFAULT_PORT;
+use server::interface;
 
-mod interface;
-
-const WORLD_UPD_RATE: Duration = Duration::from_millis(1000 / 60);  // 60 times per second
+/// How often the logs are written to the terminal
+const LOG_PRINT_RATE: Duration = Duration::from_millis(50);
+/// Where the chunks the server can't keep in memory are written
+const CHUNK_STORE_DIR: &str = "chunk_store";
 
+/// `server [address] [--ticks n]`
+/// Listens on `address`, all the interfaces on the default port if not given, and runs until interrupted or for `n` ticks
 fn main() {
-    let mut world = World::new(3, 3);
+    // There is no ui to show the logs, they are printed from their own thread so a slow terminal never delays a tick
+    std::thread::spawn(|| loop {
+        logger::print_new_logs();
+        std::thread::sleep(LOG_PRINT_RATE);
+    });
+
+    let mut addr = SocketAddr::from(([0, 0, 0, 0], DEFAULT_PORT));
+    let mut n_ticks = None;
+
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        if arg == "--ticks" {
+            n_ticks = args.next().and_then(|n| n.parse::<u64>().ok());
+            continue;
+        }
+
+        addr = match arg.parse() {
+            Ok(addr) => addr,
+            Err(err) => {
+                logger::error(0, format!("Invalid address {}: {}", arg, err));
+                exit(1);
+            },
+        };
+    }
+
+    let seed = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_nanos() as u64;
+    // Sent to the clients when they connect, so they simulate with the same one
+    let config = WorldConfig::load_or_default("world_config.json");
+    let mut world = World::new(3, 3, seed, config);
+    // Every player of the server is a connected client
+    for id in world.player_ids() {
+        world.remove_player(id);
+    }
+    // The server has authority over the asteroids, the chunks it stored are the only copy
+    world.set_chunk_store_dir(Some(CHUNK_STORE_DIR.into()));
 
-    for _x in 0..100 {
-        world.update();
+    let mut server = match interface::Server::bind(addr, world) {
+        Ok(server) => server,
+        Err(err) => {
+            logger::error(0, format!("Unable to listen on {}: {}", addr, err));
+            exit(1);
+        },
+    };
+
+    // The real address, when the port was picked by the system
+    logger::info(0, format!("Listening on {}", server.local_addr().unwrap()));
+
+    // Interrupted, the server still tells its clients it is closing
+    let stop = server.stop_flag();
+    if let Err(err) = ctrlc::set_handler(move || stop.store(true, Ordering::Relaxed)) {
+        logger::warn(0, format!("Unable to handle interruptions: {}", err));
+    }
+
+    if let Err(err) = server.run(n_ticks) {
+        logger::error(0, format!("Stopped: {}", err));
+        exit(1);
     }
+
+    exit(0);
+}
+
+/// Exits once the last logs are printed
+fn exit(code: i32) -> ! {
+    logger::print_new_logs();
+    std::process::exit(code);
 }
@@ -0,0 +1,302 @@
+use std::io::{BufRead, BufReader};
+use std::net::{SocketAddr, UdpSocket};
+use std::process::{Child, Command, Stdio};
+use std::time::{Duration, Instant};
+
+use game_logic::{EntityId, Player, PlayerInput};
+use web_types::{UpMsgBox, DownMsgBox, GameUpdate, PlayerLook, Timestamp, MAX_PACKET_SIZE, TIMEOUT, KEEP_ALIVE_RATE};
+
+/// The server process, killed when the test ends even if it fails
+struct ServerProcess {
+    child: Child,
+    addr: SocketAddr,
+}
+
+impl ServerProcess {
+    fn start() -> ServerProcess {
+        let mut child = Command::new(env!("CARGO_BIN_EXE_server"))
+            .arg("127.0.0.1:0")
+            .stdout(Stdio::piped())
+            .spawn()
+            .unwrap();
+
+        // The port is picked by the system, the server prints it once it listens
+        let mut stdout = BufReader::new(child.stdout.take().unwrap());
+        let mut line = String::new();
+        stdout.read_line(&mut line).unwrap();
+        let addr = line.trim().rsplit(' ').next().unwrap().parse().unwrap();
+
+        // The rest of the output is drained, a closed pipe would make the server fail to print
+        std::thread::spawn(move || std::io::copy(&mut stdout, &mut std::io::sink()));
+
+        return ServerProcess { child, addr };
+    }
+}
+
+impl Drop for ServerProcess {
+    fn drop(&mut self) {
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
+
+struct TestClient {
+    socket: UdpSocket,
+}
+
+impl TestClient {
+    fn new(server: &ServerProcess) -> TestClient {
+        let socket = UdpSocket::bind("127.0.0.1:0").unwrap();
+        socket.connect(server.addr).unwrap();
+        socket.set_read_timeout(Some(Duration::from_millis(50))).unwrap();
+
+        return TestClient { socket };
+    }
+
+    fn send(&self, msg: UpMsgBox) {
+        self.socket.send(&msg.to_bytes()).unwrap();
+    }
+
+    /// Receives until a message matches, or gives up after 2 seconds
+    fn wait_for<F>(&self, f: F) -> Option<DownMsgBox> where F: FnMut(&DownMsgBox) -> bool {
+        return self.wait_for_during(Duration::from_secs(2), f);
+    }
+
+    fn wait_for_during<F>(&self, duration: Duration, mut f: F) -> Option<DownMsgBox> where F: FnMut(&DownMsgBox) -> bool {
+        let deadline = Instant::now() + duration;
+        let mut buf = [0; MAX_PACKET_SIZE];
+
+        while Instant::now() < deadline {
+            if let Ok(len) = self.socket.recv(&mut buf) {
+                let msg = DownMsgBox::from_bytes(&buf[..len]).unwrap();
+                if f(&msg) {
+                    return Some(msg);
+                }
+            }
+        }
+
+        return None;
+    }
+
+    fn connect(&self) -> EntityId {
+        return self.connect_with(PlayerLook::default());
+    }
+
+    fn connect_with(&self, look: PlayerLook) -> EntityId {
+        self.send(UpMsgBox::NewConnection { look });
+
+        return match self.wait_for(|msg| matches!(msg, DownMsgBox::ConnectionAcknowleged { .. })) {
+            Some(DownMsgBox::ConnectionAcknowleged { your_id, .. }) => your_id,
+            _ => panic!("the server never acknowledged the connection"),
+        };
+    }
+}
+
+#[test]
+fn handshake_gives_a_player() {
+    let server = ServerProcess::start();
+    let client = TestClient::new(&server);
+
+    let id = client.connect();
+    assert!(!id.is_none());
+
+    // The new player is part of the broadcasted world
+    let update = client.wait_for(|msg| matches!(msg, DownMsgBox::GameUpdate(GameUpdate::PlayerUpdate { id: update_id, .. }) if *update_id == id));
+    assert!(update.is_some());
+}
+
+#[test]
+fn handshake_is_repeated_when_lost() {
+    let server = ServerProcess::start();
+    let client = TestClient::new(&server);
+
+    // A client that didn't get the acknowledgement asks again, and keeps its player
+    assert_eq!(client.connect(), client.connect());
+}
+
+#[test]
+fn inputs_move_the_player() {
+    let server = ServerProcess::start();
+    let client = TestClient::new(&server);
+    let id = client.connect();
+
+    // Due at once, the server applies it on its next tick
+    client.send(UpMsgBox::Input { input: PlayerInput { thrust: true, ..PlayerInput::new(0, id) } });
+
+    let moving = client.wait_for(|msg| match msg {
+        DownMsgBox::GameUpdate(GameUpdate::PlayerUpdate { id: update_id, player, .. }) => *update_id == id && player.vel.x != 0.,
+        _ => false,
+    });
+    assert!(moving.is_some());
+}
+
+#[test]
+fn unknown_clients_are_unrecognised() {
+    let server = ServerProcess::start();
+    let client = TestClient::new(&server);
+
+    client.send(UpMsgBox::Input { input: PlayerInput::default() });
+
+    assert!(client.wait_for(|msg| matches!(msg, DownMsgBox::Unrecognised)).is_some());
+}
+
+#[test]
+fn clients_see_each_other() {
+    let server = ServerProcess::start();
+    let first = TestClient::new(&server);
+    let second = TestClient::new(&server);
+
+    let first_id = first.connect();
+    let second_id = second.connect();
+
+    let joined = first.wait_for(|msg| matches!(msg, DownMsgBox::GameUpdate(GameUpdate::NewPlayer { id, .. }) if *id == second_id));
+    assert!(joined.is_some());
+
+    first.send(UpMsgBox::Disconect);
+
+    let left = second.wait_for(|msg| matches!(msg, DownMsgBox::GameUpdate(GameUpdate::PlayerDisconnect { id }) if *id == first_id));
+    assert!(left.is_some());
+}
+
+#[test]
+fn the_look_is_given_on_join() {
+    let server = ServerProcess::start();
+    let first = TestClient::new(&server);
+    let second = TestClient::new(&server);
+
+    let look = PlayerLook { accent_color_0: [0., 1., 0., 1.], player_img: 1, ..PlayerLook::default() };
+    first.connect();
+    let second_id = second.connect_with(look);
+
+    match first.wait_for(|msg| matches!(msg, DownMsgBox::GameUpdate(GameUpdate::NewPlayer { id, .. }) if *id == second_id)) {
+        Some(DownMsgBox::GameUpdate(GameUpdate::NewPlayer { player, .. })) => {
+            assert_eq!(player.accent_color_0, look.accent_color_0);
+            assert_eq!(player.player_img, look.player_img);
+        },
+        _ => panic!("the first client never saw the second one join"),
+    }
+}
+
+#[test]
+fn keep_alives_are_echoed() {
+    let server = ServerProcess::start();
+    let client = TestClient::new(&server);
+    client.connect();
+
+    let ticked = match client.wait_for(|msg| matches!(msg, DownMsgBox::GameUpdate(GameUpdate::PlayerUpdate { .. }))) {
+        Some(DownMsgBox::GameUpdate(GameUpdate::PlayerUpdate { time, .. })) => time,
+        _ => panic!("the server never sent the state of the world"),
+    };
+
+    let sent = Timestamp::from_micros(1234);
+    client.send(UpMsgBox::KeepAlive { time: sent });
+
+    // The time of the client comes back as it was, with the time of the server, on the same clock as the ticks
+    match client.wait_for(|msg| matches!(msg, DownMsgBox::KeepAlive { .. })) {
+        Some(DownMsgBox::KeepAlive { time, server_time }) => {
+            assert_eq!(time, sent);
+            assert!(server_time >= ticked);
+        },
+        _ => panic!("the keep alive wasn't echoed"),
+    }
+}
+
+#[test]
+fn late_player_updates_are_ignored() {
+    let server = ServerProcess::start();
+    let client = TestClient::new(&server);
+    let id = client.connect();
+
+    let mut ahead = Player::new();
+    ahead.pos.x = 5.;
+    let mut behind = Player::new();
+    behind.pos.x = -5.;
+
+    // The second update was sent first and arrives late
+    client.send(UpMsgBox::PlayerUpdate { player: ahead, time: Timestamp::of_tick(1000) });
+    client.send(UpMsgBox::PlayerUpdate { player: behind, time: Timestamp::of_tick(999) });
+
+    let is_update = |msg: &DownMsgBox| matches!(msg, DownMsgBox::GameUpdate(GameUpdate::PlayerUpdate { id: update_id, .. }) if *update_id == id);
+    let moved = client.wait_for(|msg| is_update(msg) && matches!(msg, DownMsgBox::GameUpdate(GameUpdate::PlayerUpdate { player, .. }) if player.pos.x > 0.));
+    assert!(moved.is_some());
+
+    let went_back = client.wait_for_during(Duration::from_millis(200), |msg| {
+        is_update(msg) && matches!(msg, DownMsgBox::GameUpdate(GameUpdate::PlayerUpdate { player, .. }) if player.pos.x < 0.)
+    });
+    assert!(went_back.is_none());
+}
+
+#[test]
+fn silent_clients_time_out() {
+    let server = ServerProcess::start();
+    let silent = TestClient::new(&server);
+    let watcher = TestClient::new(&server);
+
+    let silent_id = silent.connect();
+    watcher.connect();
+
+    // The watcher keeps its connection alive while it waits for the silent one to be dropped
+    let deadline = Instant::now() + TIMEOUT * 3;
+    let mut dropped = false;
+    while !dropped && Instant::now() < deadline {
+        watcher.send(UpMsgBox::KeepAlive { time: Timestamp::ZERO });
+        dropped = watcher.wait_for_during(KEEP_ALIVE_RATE, |msg| {
+            matches!(msg, DownMsgBox::GameUpdate(GameUpdate::PlayerDisconnect { id }) if *id == silent_id)
+        }).is_some();
+    }
+    assert!(dropped);
+
+    // The server forgot it
+    silent.send(UpMsgBox::KeepAlive { time: Timestamp::ZERO });
+    assert!(silent.wait_for(|msg| matches!(msg, DownMsgBox::Unrecognised)).is_some());
+}
+
+#[test]
+fn newcomers_get_the_asteroids() {
+    let server = ServerProcess::start();
+    let client = TestClient::new(&server);
+    client.connect();
+
+    let snapshot = client.wait_for(|msg| matches!(msg, DownMsgBox::GameUpdate(GameUpdate::ChunkState { asteroids, .. }) if !asteroids.is_empty()));
+    match snapshot {
+        Some(DownMsgBox::GameUpdate(GameUpdate::ChunkState { part, n_parts, asteroids, .. })) => {
+            assert!(part < n_parts);
+            assert!(asteroids.iter().all(|asteroid| !asteroid.id.is_none()));
+        },
+        msg => panic!("{:?}", msg),
+    }
+}
+
+#[test]
+fn projectiles_are_sent_to_everyone() {
+    let server = ServerProcess::start();
+    let shooter = TestClient::new(&server);
+    let watcher = TestClient::new(&server);
+    let shooter_id = shooter.connect();
+    watcher.connect();
+
+    shooter.send(UpMsgBox::Input { input: PlayerInput { fire: true, ..PlayerInput::new(0, shooter_id) } });
+
+    match watcher.wait_for(|msg| matches!(msg, DownMsgBox::GameUpdate(GameUpdate::NewProjectile { .. }))) {
+        Some(DownMsgBox::GameUpdate(GameUpdate::NewProjectile { projectile })) => {
+            assert_eq!(projectile.owner, shooter_id);
+            // The time it has left, not the time of the server it expires at
+            assert!(projectile.expire_time <= Duration::from_millis(1200));
+        },
+        msg => panic!("{:?}", msg),
+    }
+}
+
+#[cfg(unix)]
+#[test]
+fn interrupted_servers_tell_their_clients() {
+    let mut server = ServerProcess::start();
+    let client = TestClient::new(&server);
+    client.connect();
+
+    let interrupted = Command::new("kill").args(["-INT", &server.child.id().to_string()]).status().unwrap();
+    assert!(interrupted.success());
+
+    assert!(client.wait_for(|msg| matches!(msg, DownMsgBox::ServerClosing)).is_some());
+    assert!(server.child.wait().unwrap().success());
+}